@@ -0,0 +1,158 @@
+use anyhow::{bail, Context, Result};
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::state::app::App;
+use crate::state::command;
+use crate::state::filter::Filter;
+use crate::state::todo::{all_rows, Todo, TodoManager};
+
+pub const USAGE: &str = "\
+Usage: todo [COMMAND]
+
+Without a command the interactive TUI starts.
+
+Commands:
+  add [--list <name>] [--parent <id>] <text>
+                           Add a todo; the text may hold inline tokens such as
+                           due:tomorrow !high +project @tag
+  list [--json] [--list <name>] [filter]
+                           Print todos, optionally matching a filter query
+  done <id>                Complete a todo
+  rm <id>                  Move a todo and its subtasks to the trash
+  edit <id> <text>         Replace a todo's title and inline tokens
+  import <format> <file>   Merge todos from a file (formats: todotxt, md, org, ics)
+  export <format> <file>   Write all todos to a file
+  help                     Show this message";
+
+/// Runs a subcommand against the loaded todos without entering the TUI.
+/// Returns the text to print.
+pub fn run(app: &mut App, args: &[String]) -> Result<String> {
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    match args.as_slice() {
+        ["add", rest @ ..] => add(app, rest),
+        ["list", rest @ ..] => list(app, rest),
+        ["done", id] => done(app, id),
+        ["rm", id] => {
+            let id = find_id(app, id)?;
+            app.remove_todo(id);
+            Ok(format!("Removed #{}", id))
+        }
+        ["edit", id, text @ ..] if !text.is_empty() => {
+            let id = find_id(app, id)?;
+            app.edit_todo(id, &text.join(" "));
+            Ok(format!("Updated #{}", id))
+        }
+        ["import", format, path] => command::import(app, format, Path::new(path)),
+        ["export", format, path] => command::export(app, format, Path::new(path)),
+        ["help" | "--help" | "-h"] => Ok(USAGE.to_string()),
+        _ => bail!("invalid arguments: {}\n\n{}", args.join(" "), USAGE),
+    }
+}
+
+/// Whether a subcommand leaves the todos alone, so it can run while another
+/// instance holds the lock
+pub fn is_read_only(args: &[String]) -> bool {
+    matches!(args.first().map(String::as_str), Some("list" | "export" | "help" | "--help" | "-h"))
+}
+
+fn add(app: &mut App, args: &[&str]) -> Result<String> {
+    let (options, words) = options(args, &["--list", "--parent"])?;
+    if words.is_empty() {
+        bail!("nothing to add\n\n{}", USAGE);
+    }
+    let text = words.join(" ");
+    match options.get("--parent") {
+        Some(parent) => {
+            let parent = find_id(app, parent)?;
+            app.add_subtask(parent, text);
+        }
+        None => {
+            if let Some(list) = options.get("--list") {
+                app.switch_to_list(list);
+            }
+            app.add_todo(text);
+        }
+    }
+    let todo = app.get_todos().last().context("todo was not added")?;
+    Ok(format!("Added #{} to {}", todo.id, todo.list))
+}
+
+fn list(app: &App, args: &[&str]) -> Result<String> {
+    let json = args.contains(&"--json");
+    let args: Vec<&str> = args.iter().copied().filter(|a| *a != "--json").collect();
+    let (options, words) = options(&args, &["--list"])?;
+    let filter = match words.is_empty() {
+        true => None,
+        false => Some(Filter::parse(&words.join(" "))?),
+    };
+    let todos = app.get_todos();
+    let matches = |todo: &Todo| {
+        options.get("--list").map_or(true, |list| todo.list == *list)
+            && filter.as_ref().map_or(true, |f| f.matches(todo))
+    };
+
+    if json {
+        let selected: Vec<&Todo> = todos.iter().filter(|t| matches(t)).collect();
+        return serde_json::to_string_pretty(&selected).context("failed to serialize todos");
+    }
+    let mut lines = Vec::new();
+    for list in app.get_lists() {
+        let rows: Vec<_> = all_rows(todos)
+            .into_iter()
+            .filter(|row| todos[row.index].list == *list && matches(&todos[row.index]))
+            .collect();
+        if rows.is_empty() {
+            continue;
+        }
+        lines.push(format!("{}:", list));
+        for row in rows {
+            let todo = &todos[row.index];
+            lines.push(format!(
+                "{:>5} {}[{}] {}",
+                format!("#{}", todo.id),
+                "  ".repeat(row.depth),
+                if todo.is_done() { "x" } else { " " },
+                todo.to_input()
+            ));
+        }
+    }
+    Ok(lines.join("\n"))
+}
+
+fn done(app: &mut App, id: &str) -> Result<String> {
+    let id = find_id(app, id)?;
+    if app.get_todos().iter().any(|t| t.id == id && t.is_done()) {
+        bail!("#{} is already done", id);
+    }
+    app.toggle_todo(id);
+    Ok(format!("Completed #{}", id))
+}
+
+// Accepts `12` or `#12` and checks the todo exists
+fn find_id(app: &App, value: &str) -> Result<usize> {
+    let id: usize = value
+        .trim_start_matches('#')
+        .parse()
+        .with_context(|| format!("invalid todo id '{}'", value))?;
+    if !app.get_todos().iter().any(|t| t.id == id) {
+        bail!("no todo with id {}", id);
+    }
+    Ok(id)
+}
+
+// Splits `--name value` pairs for the given names from the other words
+fn options<'a>(args: &[&'a str], names: &[&str]) -> Result<(HashMap<&'a str, &'a str>, Vec<&'a str>)> {
+    let mut options = HashMap::new();
+    let mut words = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if names.contains(arg) {
+            let value = args.next().with_context(|| format!("{} needs a value", arg))?;
+            options.insert(*arg, *value);
+        } else {
+            words.push(*arg);
+        }
+    }
+    Ok((options, words))
+}
@@ -1,34 +1,112 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use crossterm::{
+    event::{DisableBracketedPaste, EnableBracketedPaste},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::{prelude::*, Terminal};
-use std::{io::stdout, path::PathBuf};
+use std::{env, io::stdout, path::PathBuf};
 
+mod cli;
 mod state;
 mod ui;
 mod utils;
 
 use state::app::App;
+use state::workflow::Workflow;
 use crate::state::todo::TodoManager;
-use utils::storage::TodoStorage;
+use utils::config::Config;
+use utils::storage::{FileFormat, TodoStorage, TodoStore};
 
 fn main() -> Result<()> {
+    let args: Vec<String> = env::args().skip(1).collect();
+
     // Initialize app with stored data
-    let storage_path = PathBuf::from("todos.json");
-    let todos = TodoStorage::load_todos(&storage_path).unwrap_or_default();
+    let config = Config::load()?;
+    let storage_path = config.storage_path();
+    let mut store = TodoStorage::open(&config.storage, storage_path.clone())?;
+    // Why this instance must not write to the store, if it must not
+    let mut read_only = store.read_only().map(|owner| format!("the store is in use by {}", owner));
+    let (mut todos, mut load_message) = match store.load() {
+        Ok(todos) => {
+            let message = store.recovered_from().map(|backup| format!(
+                "{} was unreadable; restored from {}", storage_path.display(), backup.display()
+            ));
+            (todos, message)
+        }
+        // Scripts should not silently work on an empty list
+        Err(e) if !args.is_empty() => return Err(e),
+        // Never save over a file that could not be read
+        Err(e) => {
+            read_only = Some(format!("{} could not be read", storage_path.display()));
+            (Vec::new(), Some(format!("Failed to load todos: {:#}", e)))
+        }
+    };
+
+    // Older versions kept todos.json in the working directory
+    let legacy_path = PathBuf::from("todos.json");
+    if todos.is_empty() && read_only.is_none() && config.uses_default_path() && legacy_path.exists() {
+        if let Ok(legacy) = TodoStorage::load_todos(&legacy_path, FileFormat::Json) {
+            todos = legacy;
+            // Import only once, or deleting every todo would bring them back
+            let imported = legacy_path.with_extension("json.imported");
+            load_message = Some(match std::fs::rename(&legacy_path, &imported) {
+                Ok(()) => format!("Imported ./todos.json into {}; the old file is now {}", storage_path.display(), imported.display()),
+                Err(e) => format!("Imported ./todos.json into {}, but could not rename it: {}", storage_path.display(), e),
+            });
+        }
+    }
+
     let mut app = App::with_todos(todos);
+    if let Some(message) = load_message {
+        app.set_status(message);
+    }
+    if let Some(reason) = &read_only {
+        app.set_read_only(reason.clone());
+    }
+    let sidecar = |name| store.path().with_file_name(name);
+    app.set_views(TodoStorage::load_views(&sidecar(VIEWS_FILE)).unwrap_or_default());
+    app.set_history(TodoStorage::load_history(&sidecar(HISTORY_FILE)).unwrap_or_default());
+    app.set_lists(TodoStorage::load_lists(&sidecar(LISTS_FILE)).unwrap_or_default());
+    app.set_sort_mode(TodoStorage::load_sort_mode(&sidecar(SORT_FILE)).unwrap_or_default());
+    app.set_bins(
+        TodoStorage::load_removed(&sidecar(ARCHIVE_FILE)).unwrap_or_default(),
+        TodoStorage::load_removed(&sidecar(TRASH_FILE)).unwrap_or_default(),
+    );
+    app.set_pomodoro_config(config.pomodoro.clone());
+    app.set_reminder_config(config.reminders.clone());
+    app.set_workflow(Workflow::new(&config.workflow));
+    // Only the TUI archives; a headless command leaves the store as it was
+    if let Some(days) = config.archive.after_days.filter(|_| args.is_empty()) {
+        app.archive_completed(Some(days), false);
+    }
+
+    // Subcommands run headless and exit
+    if !args.is_empty() {
+        if let Some(reason) = read_only.as_ref().filter(|_| !cli::is_read_only(&args)) {
+            anyhow::bail!("cannot change todos: {}", reason);
+        }
+        let output = cli::run(&mut app, &args)?;
+        // Saving also rotates the backups, so only commands that change todos save
+        if read_only.is_none() && !cli::is_read_only(&args) {
+            save_all(store.as_mut(), &app)?;
+        }
+        println!("{}", output);
+        return Ok(());
+    }
 
     // Setup terminal
     let mut terminal = setup_terminal()?;
     
     // Run the application
-    let result = app.run(&mut terminal);
+    let result = app.run(&mut terminal, store.as_mut());
     
-    // Save todos before exit
-    if let Err(e) = TodoStorage::save_todos(app.get_todos(), &storage_path) {
-        eprintln!("Failed to save todos: {}", e);
+    // Save todos before exit, keeping edits made elsewhere meanwhile
+    if read_only.is_none() {
+        app.merge_before_save(store.as_mut());
+        if let Err(e) = save_all(store.as_mut(), &app) {
+            eprintln!("Failed to save: {:#}", e);
+        }
     }
 
     // Cleanup terminal
@@ -37,9 +115,29 @@ fn main() -> Result<()> {
     result
 }
 
+// Files kept next to the todo data
+const VIEWS_FILE: &str = "views.json";
+const HISTORY_FILE: &str = "history.json";
+const LISTS_FILE: &str = "lists.json";
+const SORT_FILE: &str = "sort.json";
+const ARCHIVE_FILE: &str = "archive.json";
+const TRASH_FILE: &str = "trash.json";
+
+fn save_all(store: &mut dyn TodoStore, app: &App) -> Result<()> {
+    store.save(app.get_todos()).context("failed to save todos")?;
+    let sidecar = |name| store.path().with_file_name(name);
+    TodoStorage::save_views(app.get_views(), &sidecar(VIEWS_FILE))?;
+    TodoStorage::save_history(app.get_history(), &sidecar(HISTORY_FILE))?;
+    TodoStorage::save_lists(app.get_lists(), &sidecar(LISTS_FILE))?;
+    TodoStorage::save_sort_mode(app.get_sort_mode(), &sidecar(SORT_FILE))?;
+    TodoStorage::save_removed(app.get_archive(), &sidecar(ARCHIVE_FILE))?;
+    TodoStorage::save_removed(app.get_trash(), &sidecar(TRASH_FILE))?;
+    Ok(())
+}
+
 fn setup_terminal() -> Result<Terminal<impl Backend>> {
     enable_raw_mode()?;
-    execute!(stdout(), EnterAlternateScreen)?;
+    execute!(stdout(), EnterAlternateScreen, EnableBracketedPaste)?;
     
     let backend = CrosstermBackend::new(stdout());
     let terminal = Terminal::new(backend)?;
@@ -49,6 +147,6 @@ fn setup_terminal() -> Result<Terminal<impl Backend>> {
 
 fn restore_terminal() -> Result<()> {
     disable_raw_mode()?;
-    execute!(stdout(), LeaveAlternateScreen)?;
+    execute!(stdout(), DisableBracketedPaste, LeaveAlternateScreen)?;
     Ok(())
 }
\ No newline at end of file
@@ -2,12 +2,13 @@ use anyhow::Result;
 use ratatui::Terminal;
 use ratatui::Frame;
 use ratatui::backend::Backend;
-use crate::state::todo::{Todo,TodoManager};
+use ratatui::layout::{Constraint, Direction, Layout};
+use crate::state::todo::{descendant_ids, has_children, tree_rows, Priority, Todo, TodoManager, TodoRow, DEFAULT_LIST};
 use crate::ui::layout::layout2;
 use crate::ui::sections::music_browser::BrowserRenderer;
 use std::path::PathBuf;
 use std::error::Error;
-use ratatui::widgets::{Paragraph,Block,Borders};
+use ratatui::widgets::{Paragraph,Block,Borders,Clear,ListState};
 use std::path::Path;
 use std::thread;
 use std::time::Duration;
@@ -18,7 +19,20 @@ use std::io::BufRead;
 use ratatui::widgets::canvas::{Canvas,Line};
 //
 use crate::ui::layout::create_layout;
-use crate::ui::sections::todo_list::TodoListRenderer;
+use crate::ui::sections::todo_list::{centered_rect, TodoListRenderer};
+use crate::ui::sections::notes::NotesRenderer;
+use crate::ui::sections::stats::StatsRenderer;
+use crate::state::stats::Stats;
+use crate::state::pomodoro::{Phase, Pomodoro};
+use crate::ui::sections::pomodoro::PomodoroRenderer;
+use crate::state::merge::{self, Conflict};
+use crate::state::timelog::{format_duration, ReportRange, TimeEntry, TimeReport};
+use crate::ui::sections::time_report::TimeReportRenderer;
+use crate::ui::sections::board::BoardRenderer;
+use crate::state::workflow::{Workflow, DONE_STATE};
+use crate::utils::storage::TodoStore;
+use crate::utils::config::{BreakMusic, NotifyEscape, PomodoroConfig, ReminderConfig};
+use crate::utils::editor;
 use crate::state::event::EventHandler;
 use crate::state::browser::MusicBrowser;
 use crate::state::browser::{MusicItem,SongMapping};
@@ -28,13 +42,52 @@ use super::browser;
 use super::player::MusicPlayer;
 use crate::utils::art::get_album_art;
 use crate::state::event::VISIBLE_COUNT;
+use crate::state::filter::{Filter, SavedView};
+use crate::state::history::{History, TodoCommand};
+use crate::state::command;
+use crate::state::line_editor::LineEditor;
+use crate::state::sort::SortMode;
+use crate::state::archive::{bin_descendant_ids, Bin, RemovedTodo};
 
 
 pub struct App {
     todos: Vec<Todo>,
-    input: String,
+    todo_list_state: ListState,
+    input: LineEditor,
     input_mode: InputMode,
     next_id: usize,
+    pending_parent: Option<usize>,
+    editing_id: Option<usize>,
+    pending_complete: Option<usize>,
+    pending_notes: Option<usize>,
+    show_notes: bool,
+    filter: Option<Filter>,
+    views: Vec<SavedView>,
+    status: Option<String>,
+    history: History,
+    lists: Vec<String>,
+    current_list: usize,
+    sort: SortMode,
+    archive: Vec<RemovedTodo>,
+    trash: Vec<RemovedTodo>,
+    bin_state: ListState,
+    pomodoro: Option<Pomodoro>,
+    report_range: ReportRange,
+    workflow: Workflow,
+    /// Selected column and card on the board
+    board_column: usize,
+    board_row: usize,
+    pomodoro_config: PomodoroConfig,
+    reminders: Vec<usize>,
+    reminder_config: ReminderConfig,
+    /// The todos as last loaded from or saved to the store, the common
+    /// ancestor when merging external edits
+    base: Vec<Todo>,
+    conflicts: Vec<Conflict>,
+    save_pending: bool,
+    /// Why this instance may not write to the store, if it may not
+    read_only: Option<String>,
+    last_store_check: std::time::Instant,
     pub music_browser: Option<MusicBrowser>,
     pub song_mapping: Option<SongMapping>,
     pub music_player: Option<MusicPlayer>,
@@ -49,6 +102,24 @@ pub(crate) enum InputMode {
     Editing,
     Browser,
     Player,
+    Confirm,
+    Filter,
+    ViewName,
+    Command,
+    Archive,
+    Trash,
+    Stats,
+    Reminder,
+    Conflict,
+    TimeReport,
+    Board,
+}
+
+impl InputMode {
+    /// Modes where printable keys go into the input box
+    pub fn is_text_entry(&self) -> bool {
+        matches!(self, InputMode::Editing | InputMode::Filter | InputMode::ViewName | InputMode::Command)
+    }
 }
 
 impl App {
@@ -57,9 +128,38 @@ impl App {
 
         Self {
             todos: Vec::new(),
-            input: String::new(),
+            todo_list_state: ListState::default(),
+            input: LineEditor::default(),
             input_mode: InputMode::Normal,
             next_id: 1,
+            pending_parent: None,
+            editing_id: None,
+            pending_complete: None,
+            pending_notes: None,
+            show_notes: true,
+            filter: None,
+            views: Vec::new(),
+            status: None,
+            history: History::default(),
+            lists: vec![DEFAULT_LIST.to_string()],
+            current_list: 0,
+            sort: SortMode::Manual,
+            archive: Vec::new(),
+            trash: Vec::new(),
+            bin_state: ListState::default(),
+            pomodoro: None,
+            report_range: ReportRange::ThisWeek,
+            workflow: Workflow::default(),
+            board_column: 0,
+            board_row: 0,
+            pomodoro_config: PomodoroConfig::default(),
+            reminders: Vec::new(),
+            reminder_config: ReminderConfig::default(),
+            base: Vec::new(),
+            conflicts: Vec::new(),
+            save_pending: false,
+            read_only: None,
+            last_store_check: std::time::Instant::now(),
             music_browser: None,
             song_mapping: SongMapping::load_from_file("/home/vinay/songs.yaml").ok(),
             music_player: None,
@@ -71,21 +171,49 @@ impl App {
         }
     }
 
-    pub fn with_todos(todos: Vec<Todo>) -> Self {
+    pub fn with_todos(mut todos: Vec<Todo>) -> Self {
+        // Files written before positions existed load in file order
+        todos.sort_by_key(|todo| todo.position);
         let next_id = todos
         .iter()
         .map(|todo| todo.id)
         .max()
         .map(|id| id+1)
         .unwrap_or(1);
- 
-
-
-        Self {
+        let mut app = Self {
             todos,
-            input: String::new(),
+            todo_list_state: ListState::default(),
+            input: LineEditor::default(),
             input_mode: InputMode::Normal,
             next_id,
+            pending_parent: None,
+            editing_id: None,
+            pending_complete: None,
+            pending_notes: None,
+            show_notes: true,
+            filter: None,
+            views: Vec::new(),
+            status: None,
+            history: History::default(),
+            lists: vec![DEFAULT_LIST.to_string()],
+            current_list: 0,
+            sort: SortMode::Manual,
+            archive: Vec::new(),
+            trash: Vec::new(),
+            bin_state: ListState::default(),
+            pomodoro: None,
+            report_range: ReportRange::ThisWeek,
+            workflow: Workflow::default(),
+            board_column: 0,
+            board_row: 0,
+            pomodoro_config: PomodoroConfig::default(),
+            reminders: Vec::new(),
+            reminder_config: ReminderConfig::default(),
+            base: Vec::new(),
+            conflicts: Vec::new(),
+            save_pending: false,
+            read_only: None,
+            last_store_check: std::time::Instant::now(),
             music_browser: None,
             song_mapping: SongMapping::load_from_file("/home/vinay/songs.yaml").ok(),
             music_player:None,
@@ -96,9 +224,14 @@ impl App {
 
 
 
-        }
+        };
+        app.sync_lists();
+        app.renumber();
+        app.base = app.todos.clone();
+        app.clamp_selection();
+        app
     }
-    pub fn run(&mut self,terminal: &mut Terminal<impl Backend>) -> Result<()> {
+    pub fn run(&mut self,terminal: &mut Terminal<impl Backend>, store: &mut dyn TodoStore) -> Result<()> {
          let frame_time = Duration::from_millis(16);
       
          // Spawn a thread to run CAVA and update bars
@@ -112,6 +245,9 @@ impl App {
                 player.update();
            
             }
+            self.tick_pomodoro();
+            self.check_reminders();
+            self.poll_store(store);
             terminal.draw(|frame| self.render(frame))?;
          
             thread::sleep(Duration::from_millis(50));
@@ -121,6 +257,10 @@ impl App {
              if EventHandler::handle_event(self)? {
                 break;
              }
+             if let Some(id) = self.pending_notes.take() {
+                self.edit_notes(id);
+                terminal.clear()?;
+             }
              let elapsed = start.elapsed();
             if elapsed < frame_time {
                 thread::sleep(frame_time - elapsed);
@@ -133,14 +273,127 @@ impl App {
     fn render(& mut self, frame: &mut Frame) {
         
         match self.input_mode { 
-            InputMode::Normal | InputMode::Editing => {
+            InputMode::Normal | InputMode::Editing | InputMode::Confirm
+            | InputMode::Filter | InputMode::ViewName | InputMode::Command
+            | InputMode::Archive | InputMode::Trash | InputMode::Reminder
+            | InputMode::Conflict => {
                 let layout = create_layout(frame.area());
-                let todo_list = TodoListRenderer::render_todo_list(&self.todos);
-        frame.render_widget(todo_list, layout[1]);
+                let rows = self.visible_rows();
+                let mut list_title = match &self.filter {
+                    Some(filter) => format!("Todos [{}]", filter.source()),
+                    None => "Todos".to_string(),
+                };
+                if self.sort != SortMode::Manual {
+                    list_title.push_str(&format!(" (by {})", self.sort.label()));
+                }
+                let todo_list = TodoListRenderer::render_todo_list(&self.todos, &rows, list_title, self.status.as_deref(), self.workflow.initial());
+        let tabs = TodoListRenderer::render_list_tabs(&self.todos, &self.lists, self.current_list);
+        frame.render_widget(tabs, layout[1]);
+        let mut list_area = layout[2];
+        if let Some(reason) = &self.read_only {
+            let rows = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Length(1), Constraint::Min(0)])
+                .split(list_area);
+            frame.render_widget(TodoListRenderer::render_read_only_banner(reason), rows[0]);
+            list_area = rows[1];
+        }
+        if let Some(pomodoro) = &self.pomodoro {
+            let rows = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Length(3), Constraint::Min(0)])
+                .split(list_area);
+            let title = self.todos.iter().find(|t| t.id == pomodoro.todo_id).map(|t| t.title.as_str()).unwrap_or("");
+            frame.render_widget(PomodoroRenderer::render_pomodoro(pomodoro, title), rows[0]);
+            list_area = rows[1];
+        }
+        let selected = self.selected_todo_id().and_then(|id| self.todos.iter().find(|t| t.id == id));
+        match selected {
+            Some(todo) if self.show_notes => {
+                let panes = Layout::default()
+                    .direction(Direction::Horizontal)
+                    .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+                    .split(list_area);
+                frame.render_widget(NotesRenderer::render_notes(todo), panes[1]);
+                frame.render_stateful_widget(todo_list, panes[0], &mut self.todo_list_state);
+            }
+            _ => frame.render_stateful_widget(todo_list, list_area, &mut self.todo_list_state),
+        }
 
-        let input_block = TodoListRenderer::render_input_block(&self.input, self.input_mode);
+        let input_title = match self.input_mode {
+            InputMode::Filter => "Filter (+project @tag due<7d priority>=high -done)",
+            InputMode::ViewName => "Save view as",
+            InputMode::Command => "Command (import/export <format> <file>, move <list>, list new|rm|rename)",
+            _ if self.editing_id.is_some() => "Edit todo",
+            _ if self.pending_parent.is_some() => "Add subtask",
+            _ => "Add todo",
+        };
+        // Scroll long input so the cursor stays inside the box
+        let inner_width = layout[0].width.saturating_sub(2).max(1) as usize;
+        let cursor_column = self.input.cursor_column();
+        let scroll = (cursor_column + 1).saturating_sub(inner_width);
+        let input_block = TodoListRenderer::render_input_block(self.input.text(), input_title, self.input_mode, scroll as u16);
         frame.render_widget(input_block, layout[0]);
+        if self.input_mode.is_text_entry() {
+            frame.set_cursor_position((
+                layout[0].x + 1 + (cursor_column - scroll) as u16,
+                layout[0].y + 1,
+            ));
+        }
+
+        if let Some(id) = self.pending_complete {
+            let open = descendant_ids(&self.todos, id)
+                .iter()
+                .filter(|child| self.todos.iter().any(|t| t.id == **child && !t.is_done()))
+                .count();
+            TodoListRenderer::render_confirm(frame, &format!("Also complete {} open subtask(s)? (y/n, Esc to cancel)", open));
+        }
+
+        if let (InputMode::Reminder, Some(todo)) = (self.input_mode, self.current_reminder()) {
+            TodoListRenderer::render_reminder(frame, todo);
+        }
+
+        if let (InputMode::Conflict, Some(conflict)) = (self.input_mode, self.conflicts.first()) {
+            TodoListRenderer::render_conflict(frame, conflict, self.conflicts.len());
+        }
+
+        if let Some(bin) = self.open_bin() {
+            let area = centered_rect(frame.area(), 70, frame.area().height * 3 / 4);
+            let entries = match bin {
+                Bin::Archive => &self.archive,
+                Bin::Trash => &self.trash,
+            };
+            let list = TodoListRenderer::render_bin(entries, bin);
+            frame.render_widget(Clear, area);
+            frame.render_stateful_widget(list, area, &mut self.bin_state);
+        }
+
+            }
+
+            InputMode::Stats => {
+                // Archived todos still count towards the history
+                let todos = self.todos.iter().chain(self.archive.iter().map(|e| &e.todo));
+                let stats = Stats::compute(todos, Stats::today());
+                StatsRenderer::render_stats(frame, frame.area(), &stats);
+            }
 
+            InputMode::Board => {
+                let columns = self.board_columns();
+                let columns: Vec<(&str, Vec<&Todo>)> = self
+                    .workflow
+                    .states()
+                    .iter()
+                    .zip(columns)
+                    .map(|(state, cards)| (state.as_str(), cards.into_iter().map(|index| &self.todos[index]).collect()))
+                    .collect();
+                BoardRenderer::render_board(frame, frame.area(), &columns, self.board_column, self.board_row, self.current_list_name());
+            }
+
+            InputMode::TimeReport => {
+                let todos = self.todos.iter().chain(self.archive.iter().map(|e| &e.todo));
+                let now = chrono::Local::now();
+                let report = TimeReport::compute(todos, self.report_range, now);
+                TimeReportRenderer::render_report(frame, frame.area(), &report, &self.report_range.label(now.date_naive()));
             }
 
             InputMode::Browser | InputMode::Player => {
@@ -259,17 +512,1286 @@ impl App {
         self.input_mode = mode;
     }
     pub fn get_input(&self) -> &str {
-        &self.input
+        self.input.text()
+    }
+
+    pub fn input_editor(&mut self) -> &mut LineEditor {
+        &mut self.input
     }
 
     pub fn clear_input(&mut self) {
         self.input.clear();
     }
     pub fn push_to_input(&mut self , c: char) {
-        self.input.push(c);
+        self.input.insert_char(c);
     }
     pub fn pop_from_input(&mut self) {
-        self.input.pop();
+        self.input.backspace();
+    }
+
+    // Records the submitted line for Up/Down recall and empties the box
+    fn finish_input(&mut self) {
+        let line = self.input.text().to_string();
+        self.input.push_history(&line);
+        self.clear_input();
+    }
+
+    /// The rows currently shown in the todo list, in display order
+    pub fn visible_rows(&self) -> Vec<TodoRow> {
+        let list = self.current_list_name();
+        tree_rows(&self.todos, self.sort)
+            .into_iter()
+            .filter(|row| self.todos[row.index].list == list)
+            .filter(|row| self.filter.as_ref().map_or(true, |f| f.matches(&self.todos[row.index])))
+            .collect()
+    }
+
+    pub fn current_list_name(&self) -> &str {
+        &self.lists[self.current_list]
+    }
+
+    pub fn get_lists(&self) -> &[String] {
+        &self.lists
+    }
+
+    /// Restores the saved list order; lists only known from todos are added
+    pub fn set_lists(&mut self, lists: Vec<String>) {
+        if !lists.is_empty() {
+            self.lists = lists;
+        }
+        self.current_list = 0;
+        self.sync_lists();
+        self.todo_list_state.select(Some(0));
+        self.clamp_selection();
+    }
+
+    // Every list a todo refers to must have a tab, e.g. after an undo
+    fn sync_lists(&mut self) {
+        for todo in &self.todos {
+            if !self.lists.contains(&todo.list) {
+                self.lists.push(todo.list.clone());
+            }
+        }
+        if self.lists.is_empty() {
+            self.lists.push(DEFAULT_LIST.to_string());
+        }
+        self.current_list = self.current_list.min(self.lists.len() - 1);
+    }
+
+    /// Switches to the next (or previous) list tab
+    pub fn cycle_list(&mut self, forward: bool) {
+        let count = self.lists.len();
+        self.current_list = if forward {
+            (self.current_list + 1) % count
+        } else {
+            (self.current_list + count - 1) % count
+        };
+        self.todo_list_state.select(Some(0));
+        self.clamp_selection();
+    }
+
+    /// Makes `name` the current list, creating it if needed
+    pub fn switch_to_list(&mut self, name: &str) {
+        match self.lists.iter().position(|l| l == name) {
+            Some(index) => self.current_list = index,
+            None => {
+                self.lists.push(name.to_string());
+                self.current_list = self.lists.len() - 1;
+            }
+        }
+        self.todo_list_state.select(Some(0));
+        self.clamp_selection();
+    }
+
+    pub fn create_list(&mut self, name: &str) -> Result<()> {
+        let name = name.trim();
+        if name.is_empty() {
+            anyhow::bail!("list name is empty");
+        }
+        if self.lists.iter().any(|l| l == name) {
+            anyhow::bail!("list '{}' already exists", name);
+        }
+        self.lists.push(name.to_string());
+        self.current_list = self.lists.len() - 1;
+        self.clamp_selection();
+        Ok(())
+    }
+
+    /// Deletes an empty list
+    pub fn remove_list(&mut self, name: &str) -> Result<()> {
+        let Some(index) = self.lists.iter().position(|l| l == name) else {
+            anyhow::bail!("no list named '{}'", name);
+        };
+        let count = self.todos.iter().filter(|t| t.list == name).count();
+        if count > 0 {
+            anyhow::bail!("list '{}' still has {} todo(s); move or delete them first", name, count);
+        }
+        self.lists.remove(index);
+        self.sync_lists();
+        self.clamp_selection();
+        Ok(())
+    }
+
+    pub fn rename_list(&mut self, old: &str, new: &str) -> Result<()> {
+        let new = new.trim();
+        if !self.lists.iter().any(|l| l == old) {
+            anyhow::bail!("no list named '{}'", old);
+        }
+        if new.is_empty() || self.lists.iter().any(|l| l == new) {
+            anyhow::bail!("cannot rename '{}' to '{}'", old, new);
+        }
+        self.execute(TodoCommand::RenameList {
+            old: old.to_string(),
+            new: new.to_string(),
+        });
+        Ok(())
+    }
+
+    /// Moves a todo and its subtasks to another list, creating the list if
+    /// needed. A moved subtask becomes a top-level todo there.
+    pub fn move_to_list(&mut self, id: usize, list: &str) -> Result<()> {
+        let list = list.trim();
+        if list.is_empty() {
+            anyhow::bail!("list name is empty");
+        }
+        if !self.todos.iter().any(|t| t.id == id) {
+            anyhow::bail!("no todo selected");
+        }
+        if !self.lists.iter().any(|l| l == list) {
+            self.lists.push(list.to_string());
+        }
+        let mut commands: Vec<TodoCommand> = self
+            .update_command(id, |todo| {
+                todo.list = list.to_string();
+                todo.parent_id = None;
+            })
+            .into_iter()
+            .collect();
+        for child in descendant_ids(&self.todos, id) {
+            commands.extend(self.update_command(child, |todo| todo.list = list.to_string()));
+        }
+        self.execute(TodoCommand::Batch(commands));
+        Ok(())
+    }
+
+    pub fn selected_todo_id(&self) -> Option<usize> {
+        let rows = self.visible_rows();
+        self.todo_list_state
+            .selected()
+            .and_then(|index| rows.get(index))
+            .map(|row| self.todos[row.index].id)
+    }
+
+    pub fn select_todo(&mut self, id: usize) {
+        let rows = self.visible_rows();
+        if let Some(position) = rows.iter().position(|row| self.todos[row.index].id == id) {
+            self.todo_list_state.select(Some(position));
+        }
+    }
+
+    pub fn select_next_todo(&mut self) {
+        let count = self.visible_rows().len();
+        if count == 0 {
+            return;
+        }
+        let next = match self.todo_list_state.selected() {
+            Some(index) => (index + 1).min(count - 1),
+            None => 0,
+        };
+        self.todo_list_state.select(Some(next));
+    }
+
+    pub fn select_previous_todo(&mut self) {
+        if self.visible_rows().is_empty() {
+            return;
+        }
+        let previous = match self.todo_list_state.selected() {
+            Some(index) => index.saturating_sub(1),
+            None => 0,
+        };
+        self.todo_list_state.select(Some(previous));
+    }
+
+    pub fn set_collapsed(&mut self, id: usize, collapsed: bool) {
+        if !has_children(&self.todos, id) {
+            return;
+        }
+        if let Some(todo) = self.todos.iter_mut().find(|t| t.id == id) {
+            todo.collapsed = collapsed;
+        }
+        self.select_todo(id);
+    }
+
+    pub fn toggle_collapsed(&mut self, id: usize) {
+        if let Some(collapsed) = self.todos.iter().find(|t| t.id == id).map(|t| t.collapsed) {
+            self.set_collapsed(id, !collapsed);
+        }
+    }
+
+    /// Toggles a todo, asking first when completing a parent that still has
+    /// open subtasks.
+    pub fn request_toggle(&mut self, id: usize) {
+        let Some(todo) = self.todos.iter().find(|t| t.id == id) else {
+            return;
+        };
+        let open_descendants = descendant_ids(&self.todos, id)
+            .iter()
+            .any(|child| self.todos.iter().any(|t| t.id == *child && !t.is_done()));
+        if !todo.is_done() && open_descendants {
+            self.pending_complete = Some(id);
+            self.set_input_mode(InputMode::Confirm);
+        } else {
+            self.toggle_todo(id);
+        }
+    }
+
+    /// Answers the prompt raised by `request_toggle`
+    pub fn resolve_pending_complete(&mut self, include_descendants: bool) {
+        if let Some(id) = self.pending_complete.take() {
+            let mut ids = vec![id];
+            if include_descendants {
+                ids.extend(descendant_ids(&self.todos, id));
+            }
+            let ids: Vec<usize> = ids
+                .into_iter()
+                .filter(|&child| child == id || self.todos.iter().any(|t| t.id == child && !t.is_done()))
+                .collect();
+            let commands = ids.into_iter().flat_map(|child| self.toggle_commands(child)).collect();
+            self.execute(TodoCommand::Batch(commands));
+        }
+        self.set_input_mode(InputMode::Normal);
+    }
+
+    pub fn cancel_pending_complete(&mut self) {
+        self.pending_complete = None;
+        self.set_input_mode(InputMode::Normal);
+    }
+
+    /// Starts the input box, optionally for a subtask of `parent_id`
+    pub fn start_input(&mut self, parent_id: Option<usize>) {
+        self.pending_parent = parent_id;
+        self.editing_id = None;
+        self.clear_input();
+        self.set_input_mode(InputMode::Editing);
+    }
+
+    /// Opens the input box on an existing todo, inline tokens included
+    pub fn start_edit(&mut self, id: usize) {
+        if let Some(todo) = self.todos.iter().find(|t| t.id == id) {
+            let text = todo.to_input();
+            self.input.set_text(text);
+            self.editing_id = Some(id);
+            self.pending_parent = None;
+            self.set_input_mode(InputMode::Editing);
+        }
+    }
+
+    /// Adds (or, when editing, updates) the todo in the input box and
+    /// returns to normal mode
+    pub fn submit_input(&mut self) {
+        if self.input.is_empty() {
+            return;
+        }
+        let title = self.input.text().to_string();
+        match (self.editing_id.take(), self.pending_parent.take()) {
+            (Some(id), _) => self.edit_todo(id, &title),
+            (None, Some(parent_id)) => self.add_subtask(parent_id, title),
+            (None, None) => self.add_todo(title),
+        }
+        self.finish_input();
+        self.set_input_mode(InputMode::Normal);
+    }
+
+    /// Replaces the title and inline-token fields of a todo with those
+    /// parsed from `input`
+    pub fn edit_todo(&mut self, id: usize, input: &str) {
+        let parsed = Todo::from_input(id, input);
+        self.update_todo(id, |todo| {
+            todo.title = parsed.title;
+            todo.project = parsed.project;
+            todo.tags = parsed.tags;
+            todo.priority = parsed.priority;
+            todo.due = parsed.due;
+            todo.scheduled = parsed.scheduled;
+            todo.recurrence = parsed.recurrence;
+            // A new reminder time fires again
+            todo.reminder_dismissed &= todo.remind_at == parsed.remind_at;
+            todo.remind_at = parsed.remind_at;
+        });
+    }
+
+    pub fn cancel_input(&mut self) {
+        self.pending_parent = None;
+        self.editing_id = None;
+        self.clear_input();
+        self.set_input_mode(InputMode::Normal);
+    }
+
+    /// Asks the run loop to open the notes of `id` in an external editor
+    pub fn request_notes_edit(&mut self, id: usize) {
+        self.pending_notes = Some(id);
+    }
+
+    // Runs the editor with the TUI suspended and records the result for undo
+    fn edit_notes(&mut self, id: usize) {
+        let Some(notes) = self.todos.iter().find(|t| t.id == id).map(|t| t.notes.clone()) else {
+            return;
+        };
+        match editor::edit_text(&notes) {
+            Ok(edited) if edited != notes => {
+                self.update_todo(id, |todo| todo.notes = edited);
+                self.set_status("Notes saved");
+            }
+            Ok(_) => self.set_status("Notes unchanged"),
+            Err(e) => self.set_status(format!("Error: {:#}", e)),
+        }
+    }
+
+    pub fn toggle_notes_pane(&mut self) {
+        self.show_notes = !self.show_notes;
+    }
+
+    pub fn set_pomodoro_config(&mut self, config: PomodoroConfig) {
+        self.pomodoro_config = config;
+    }
+
+    pub fn workflow(&self) -> &Workflow {
+        &self.workflow
+    }
+
+    pub fn set_workflow(&mut self, workflow: Workflow) {
+        self.workflow = workflow;
+        let bins = self.archive.iter_mut().chain(self.trash.iter_mut()).map(|e| &mut e.todo);
+        self.workflow.normalize(self.todos.iter_mut().chain(bins));
+        // Like the todos, so normalizing alone does not count as our change
+        // when merging outside edits
+        self.workflow.normalize(&mut self.base);
+    }
+
+    /// Indices of the todos on the board, one list per workflow state. The
+    /// board shows the current list with the filter and sort applied, like
+    /// the list view, but without the tree.
+    fn board_columns(&self) -> Vec<Vec<usize>> {
+        let mut columns = vec![Vec::new(); self.workflow.states().len()];
+        for row in self.visible_board_rows() {
+            columns[self.workflow.column(&self.todos[row])].push(row);
+        }
+        columns
+    }
+
+    fn visible_board_rows(&self) -> Vec<usize> {
+        let list = self.current_list_name();
+        let mut rows: Vec<usize> = (0..self.todos.len())
+            .filter(|&index| self.todos[index].list == list)
+            .filter(|&index| self.filter.as_ref().map_or(true, |f| f.matches(&self.todos[index])))
+            .collect();
+        rows.sort_by(|&a, &b| self.sort.compare(&self.todos[a], &self.todos[b]));
+        rows
+    }
+
+    fn selected_card(&self) -> Option<usize> {
+        let columns = self.board_columns();
+        let cards = columns.get(self.board_column)?;
+        cards.get(self.board_row.min(cards.len().saturating_sub(1))).map(|&index| self.todos[index].id)
+    }
+
+    /// Opens the board on the column of the todo selected in the list
+    pub fn open_board(&mut self) {
+        self.board_column = 0;
+        self.board_row = 0;
+        if let Some(id) = self.selected_todo_id() {
+            self.select_card(id);
+        }
+        self.set_input_mode(InputMode::Board);
+    }
+
+    /// Leaves the board with the same todo selected in the list
+    pub fn close_board(&mut self) {
+        if let Some(id) = self.selected_card() {
+            self.select_todo(id);
+        }
+        self.set_input_mode(InputMode::Normal);
+    }
+
+    fn select_card(&mut self, id: usize) {
+        for (column, cards) in self.board_columns().into_iter().enumerate() {
+            if let Some(row) = cards.iter().position(|&index| self.todos[index].id == id) {
+                self.board_column = column;
+                self.board_row = row;
+            }
+        }
+    }
+
+    pub fn move_board_selection(&mut self, columns: isize, rows: isize) {
+        let board = self.board_columns();
+        self.board_column = (self.board_column as isize + columns).clamp(0, board.len() as isize - 1) as usize;
+        let cards = board[self.board_column].len();
+        self.board_row = match columns {
+            // Keep the row when changing columns, as far as the column allows
+            0 => (self.board_row as isize + rows).clamp(0, cards.saturating_sub(1) as isize) as usize,
+            _ => self.board_row.min(cards.saturating_sub(1)),
+        };
+    }
+
+    /// Moves the selected card to the previous or next workflow state
+    pub fn move_card(&mut self, offset: isize) {
+        let Some(id) = self.selected_card() else {
+            return;
+        };
+        let states = self.workflow.states();
+        let target = (self.board_column as isize + offset).clamp(0, states.len() as isize - 1) as usize;
+        if target == self.board_column {
+            return;
+        }
+        let state = states[target].clone();
+        self.set_todo_state(id, &state);
+        self.select_card(id);
+    }
+
+    /// The configured state whose name matches `name`, ignoring case
+    pub fn find_state(&self, name: &str) -> Option<String> {
+        self.workflow.states().iter().find(|s| s.eq_ignore_ascii_case(name)).cloned()
+    }
+
+    /// Moves a todo to a workflow state, completing or reopening it as needed
+    pub fn set_todo_state(&mut self, id: usize, state: &str) {
+        let commands = self.state_commands(id, state);
+        self.execute(TodoCommand::Batch(commands));
+    }
+
+    /// Starts timing `id`, or stops its timer if it runs. Only one timer runs
+    /// at a time, so starting one stops any other.
+    pub fn toggle_timer(&mut self, id: usize) {
+        let now = chrono::Local::now();
+        let running = self.todos.iter().find(|t| t.running_timer().is_some()).map(|t| t.id);
+        let mut commands = Vec::new();
+        if let Some(running) = running {
+            commands.extend(self.update_command(running, |todo| todo.stop_timer(now)));
+        }
+        if running != Some(id) {
+            commands.extend(self.update_command(id, |todo| todo.time_entries.push(TimeEntry::start(now))));
+        }
+        self.execute(TodoCommand::Batch(commands));
+        if let Some(todo) = self.todos.iter().find(|t| t.id == id) {
+            let message = match todo.running_timer() {
+                Some(_) => format!("Timer started on '{}'", todo.title),
+                None => format!("Timer stopped; {} tracked on '{}'", format_duration(todo.tracked_time(now)), todo.title),
+            };
+            self.set_status(message);
+        }
+    }
+
+    /// Shows the time report, for `range` if given or else the last one used
+    pub fn open_time_report(&mut self, range: Option<ReportRange>) {
+        if let Some(range) = range {
+            self.report_range = range;
+        }
+        self.set_input_mode(InputMode::TimeReport);
+    }
+
+    pub fn cycle_report_range(&mut self) {
+        self.report_range = self.report_range.next();
+    }
+
+    /// Writes the report for the current range as CSV
+    pub fn export_time_report(&self, path: &Path) -> Result<String> {
+        let todos = self.todos.iter().chain(self.archive.iter().map(|e| &e.todo));
+        let now = chrono::Local::now();
+        let report = TimeReport::compute(todos, self.report_range, now);
+        report.write_csv(path)?;
+        Ok(format!(
+            "Exported {} of {} to {}",
+            format_duration(report.total),
+            self.report_range.label(now.date_naive()),
+            path.display()
+        ))
+    }
+
+    /// Starts a Pomodoro on `id`, or pauses/resumes the running one
+    pub fn toggle_pomodoro(&mut self, id: usize) {
+        if let Some(pomodoro) = &mut self.pomodoro {
+            pomodoro.toggle_pause();
+            let paused = pomodoro.is_paused();
+            let phase = pomodoro.phase;
+            if let Some(player) = &mut self.music_player {
+                if phase == Phase::Focus {
+                    player.set_paused(paused);
+                }
+            }
+            self.set_status(if paused { "Pomodoro paused" } else { "Pomodoro resumed" });
+            return;
+        }
+        let mut pomodoro = Pomodoro::new(id, &self.pomodoro_config);
+        if self.music_player.is_none() && !pomodoro.tracks.is_empty() {
+            match self.play_track(&pomodoro.tracks[0]) {
+                Ok(()) => pomodoro.owns_music = true,
+                Err(e) => self.set_status(format!("Could not play focus music: {:#}", e)),
+            }
+        }
+        self.pomodoro = Some(pomodoro);
+        self.set_focus_music(true);
+    }
+
+    pub fn stop_pomodoro(&mut self) -> Result<String> {
+        let Some(pomodoro) = self.pomodoro.take() else {
+            anyhow::bail!("no Pomodoro is running");
+        };
+        if pomodoro.owns_music {
+            let _ = self.cleanup_music_player();
+        } else {
+            self.set_focus_music(true);
+        }
+        Ok(format!("Pomodoro stopped after {} session(s)", pomodoro.completed))
+    }
+
+    // Called every frame: switches phases, logs finished focus sessions and
+    // keeps the focus playlist going
+    fn tick_pomodoro(&mut self) {
+        let Some(pomodoro) = &mut self.pomodoro else {
+            return;
+        };
+        let todo_id = pomodoro.todo_id;
+        let ended = pomodoro.tick();
+
+        let finished_track = pomodoro.owns_music
+            && self.music_player.as_ref().is_some_and(|p| !p.is_paused && p.get_playback_progress() >= 1.0);
+        if finished_track {
+            pomodoro.track = (pomodoro.track + 1) % pomodoro.tracks.len();
+            let track = pomodoro.tracks[pomodoro.track].clone();
+            if let Err(e) = self.play_track(&track) {
+                self.set_status(format!("Could not play focus music: {:#}", e));
+            }
+        }
+
+        match ended {
+            Some(Phase::Focus) => {
+                self.update_todo(todo_id, |todo| todo.pomodoros.push(chrono::Local::now()));
+                self.set_focus_music(false);
+                self.set_status("Focus session done, take a break");
+            }
+            Some(Phase::Break) => {
+                self.set_focus_music(true);
+                self.set_status("Break over, back to focus");
+            }
+            None => {}
+        }
+    }
+
+    // Full volume while focusing; paused or quieter on breaks, per the config
+    fn set_focus_music(&mut self, focus: bool) {
+        let Some(player) = &mut self.music_player else {
+            return;
+        };
+        match (focus, self.pomodoro_config.on_break) {
+            (true, _) => {
+                player.set_paused(false);
+                player.set_volume(1.0);
+            }
+            (false, BreakMusic::Pause) => player.set_paused(true),
+            (false, BreakMusic::Lower) => player.set_volume(self.pomodoro_config.break_volume),
+            (false, BreakMusic::Keep) => {}
+        }
+    }
+
+    fn play_track(&mut self, track: &Path) -> Result<()> {
+        let path = track.to_string_lossy().to_string();
+        // Tracks without embedded art still play
+        let album_art = get_album_art(&path).unwrap_or_else(|_| DynamicImage::new_rgb8(1, 1));
+        if let Some(player) = &mut self.music_player {
+            player.cleanup();
+        }
+        self.music_player = Some(MusicPlayer::new(album_art, &path)?);
+        Ok(())
+    }
+
+    pub fn set_reminder_config(&mut self, config: ReminderConfig) {
+        self.reminder_config = config;
+    }
+
+    // Called every frame. Queues todos whose reminder time has passed, which
+    // includes reminders that came due while the app was closed, and shows
+    // the queue once the user is back in normal mode.
+    fn check_reminders(&mut self) {
+        let now = chrono::Local::now();
+        let due: Vec<(usize, String)> = self
+            .todos
+            .iter()
+            .filter(|t| !t.is_done() && !t.reminder_dismissed && t.remind_at.is_some_and(|at| at <= now))
+            .filter(|t| !self.reminders.contains(&t.id))
+            .map(|t| (t.id, t.title.clone()))
+            .collect();
+        for (id, title) in due {
+            self.reminders.push(id);
+            self.notify(&title);
+        }
+        // Drop reminders for todos completed or deleted in the meantime
+        let todos = &self.todos;
+        self.reminders.retain(|id| {
+            todos.iter().any(|t| t.id == *id && !t.is_done() && !t.reminder_dismissed && t.remind_at.is_some())
+        });
+        if !self.reminders.is_empty() && matches!(self.input_mode, InputMode::Normal) {
+            self.set_input_mode(InputMode::Reminder);
+        }
+        if self.reminders.is_empty() && matches!(self.input_mode, InputMode::Reminder) {
+            self.set_input_mode(InputMode::Normal);
+        }
+    }
+
+    // Bell and desktop notification escapes, as configured
+    fn notify(&self, title: &str) {
+        use std::io::Write;
+        let mut escapes = String::new();
+        if self.reminder_config.bell {
+            escapes.push('\x07');
+        }
+        // Control characters in the title would end the sequence early
+        let title: String = title.chars().filter(|c| !c.is_control()).collect();
+        match self.reminder_config.notify {
+            Some(NotifyEscape::Osc9) => escapes.push_str(&format!("\x1b]9;Reminder: {}\x07", title)),
+            Some(NotifyEscape::Osc777) => escapes.push_str(&format!("\x1b]777;notify;Todo reminder;{}\x07", title)),
+            None => {}
+        }
+        let mut stdout = std::io::stdout();
+        let _ = stdout.write_all(escapes.as_bytes());
+        let _ = stdout.flush();
+    }
+
+    pub fn current_reminder(&self) -> Option<&Todo> {
+        let id = self.reminders.first()?;
+        self.todos.iter().find(|t| t.id == *id)
+    }
+
+    /// Marks the reminder on screen as seen so it does not fire again. The
+    /// time stays so later occurrences of a recurring todo get one too.
+    pub fn dismiss_reminder(&mut self) {
+        if let Some(id) = self.reminders.first().copied() {
+            self.reminders.remove(0);
+            self.update_todo(id, |todo| todo.reminder_dismissed = true);
+        }
+    }
+
+    /// Fires the reminder on screen again after the configured delay
+    pub fn snooze_reminder(&mut self) {
+        if let Some(id) = self.reminders.first().copied() {
+            self.reminders.remove(0);
+            let at = chrono::Local::now() + chrono::Duration::minutes(self.reminder_config.snooze_minutes);
+            self.update_todo(id, |todo| {
+                todo.remind_at = Some(at);
+                todo.reminder_dismissed = false;
+            });
+            self.set_status(format!("Snoozed until {}", at.format("%H:%M")));
+        }
+    }
+
+    // Called every frame; looks at the store about once a second. Merging
+    // waits for normal mode so it never interrupts typing.
+    fn poll_store(&mut self, store: &mut dyn TodoStore) {
+        if std::mem::take(&mut self.save_pending) {
+            self.save_merged(store);
+        }
+        if self.last_store_check.elapsed() < Duration::from_secs(1) || !matches!(self.input_mode, InputMode::Normal) {
+            return;
+        }
+        self.last_store_check = std::time::Instant::now();
+        if store.changed_externally() {
+            self.merge_from_store(store);
+        }
+    }
+
+    /// Merges edits made to the store by someone else into the list. Returns
+    /// false if they conflict with ours and need resolving first.
+    pub fn merge_from_store(&mut self, store: &mut dyn TodoStore) -> bool {
+        let mut theirs = match store.reload() {
+            Ok(todos) => todos,
+            Err(e) => {
+                self.set_status(format!("Could not reload {} yet: {:#}", store.path().display(), e));
+                return true;
+            }
+        };
+        self.workflow.normalize(&mut theirs);
+        // Ids new on their side must not be handed out again here
+        let max_id = self.base.iter().chain(&self.todos).chain(&theirs).map(|t| t.id).max().unwrap_or(0);
+        self.next_id = self.next_id.max(max_id + 1);
+        let changed = merge::changed_ids(&self.base, &theirs);
+        let (merged, conflicts) = merge::merge(&self.base, &self.todos, &theirs, &mut self.next_id);
+        self.todos = merged;
+        self.base = theirs;
+        self.conflicts = conflicts;
+        // Undo replays whole-todo snapshots, which would revert what was
+        // just merged in
+        self.history.forget(&changed);
+        self.sync_lists();
+        self.renumber();
+        self.clamp_selection();
+        if self.conflicts.is_empty() {
+            self.set_status("Merged changes made outside the app");
+            self.save_merged(store);
+            true
+        } else {
+            self.set_input_mode(InputMode::Conflict);
+            false
+        }
+    }
+
+    // Writes the merged list so the store and the other side see it too
+    fn save_merged(&mut self, store: &mut dyn TodoStore) {
+        if self.read_only.is_some() || store.read_only().is_some() {
+            return;
+        }
+        match store.save(&self.todos) {
+            Ok(()) => self.base = self.todos.clone(),
+            Err(e) => self.set_status(format!("Failed to save merged todos: {:#}", e)),
+        }
+    }
+
+    /// Picks a side for the conflict on screen
+    pub fn resolve_conflict(&mut self, keep_ours: bool) {
+        if self.conflicts.is_empty() {
+            return;
+        }
+        let conflict = self.conflicts.remove(0);
+        let id = conflict.id();
+        if !keep_ours {
+            let position = self.todos.iter().position(|t| t.id == id);
+            match (conflict.theirs, position) {
+                (Some(theirs), Some(position)) => self.todos[position] = theirs,
+                (Some(theirs), None) => self.todos.push(theirs),
+                (None, Some(position)) => {
+                    self.todos.remove(position);
+                }
+                (None, None) => {}
+            }
+            self.sync_lists();
+            self.renumber();
+            self.clamp_selection();
+        }
+        if self.conflicts.is_empty() {
+            self.set_status("Conflicts resolved");
+            self.set_input_mode(InputMode::Normal);
+            // Saved from the run loop, which has the store
+            self.save_pending = true;
+        }
+    }
+
+    /// Called before the final save: takes in outside edits, keeping ours
+    /// wherever both sides changed the same todo
+    pub fn merge_before_save(&mut self, store: &mut dyn TodoStore) {
+        if store.changed_externally() && !self.merge_from_store(store) {
+            while !self.conflicts.is_empty() {
+                self.resolve_conflict(true);
+            }
+        }
+    }
+
+    /// Stops this instance from writing to the store, for the given reason
+    pub fn set_read_only(&mut self, reason: impl Into<String>) {
+        self.read_only = Some(reason.into());
+    }
+
+    pub fn set_bins(&mut self, archive: Vec<RemovedTodo>, trash: Vec<RemovedTodo>) {
+        self.archive = archive;
+        self.trash = trash;
+        // Keep ids unique so restored todos can usually keep theirs
+        let max_id = self.archive.iter().chain(&self.trash).map(|e| e.todo.id).max().unwrap_or(0);
+        self.next_id = self.next_id.max(max_id + 1);
+    }
+
+    pub fn get_archive(&self) -> &[RemovedTodo] {
+        &self.archive
+    }
+
+    pub fn get_trash(&self) -> &[RemovedTodo] {
+        &self.trash
+    }
+
+    /// Moves completed todos to the archive, each with its subtasks once all of
+    /// them are done. `older_than_days` limits it to todos completed at least
+    /// that long ago; `current_list_only` to the list on screen.
+    pub fn archive_completed(&mut self, older_than_days: Option<u32>, current_list_only: bool) -> usize {
+        let now = chrono::Local::now();
+        let list = self.current_list_name().to_string();
+        let mut ids: Vec<usize> = Vec::new();
+        for todo in &self.todos {
+            let old_enough = match (older_than_days, todo.completed_at) {
+                (None, _) => true,
+                (Some(days), Some(at)) => now - at >= chrono::Duration::days(days as i64),
+                (Some(_), None) => false,
+            };
+            if !todo.is_done() || !old_enough || (current_list_only && todo.list != list) {
+                continue;
+            }
+            let descendants = descendant_ids(&self.todos, todo.id);
+            let all_done = descendants
+                .iter()
+                .all(|child| self.todos.iter().any(|t| t.id == *child && t.is_done()));
+            if all_done {
+                ids.push(todo.id);
+                ids.extend(descendants);
+            }
+        }
+        // Remove from the back so the recorded indices stay valid on undo
+        let commands: Vec<TodoCommand> = self
+            .todos
+            .iter()
+            .enumerate()
+            .rev()
+            .filter(|(_, todo)| ids.contains(&todo.id))
+            .map(|(index, todo)| TodoCommand::Remove { index, todo: todo.clone(), bin: Some(Bin::Archive) })
+            .collect();
+        let count = commands.len();
+        self.execute(TodoCommand::Batch(commands));
+        count
+    }
+
+    pub fn sweep_completed(&mut self) {
+        let count = self.archive_completed(None, true);
+        self.set_status(format!("Archived {} todo(s)", count));
+    }
+
+    pub fn open_bin_view(&mut self, bin: Bin) {
+        let empty = match bin {
+            Bin::Archive => self.archive.is_empty(),
+            Bin::Trash => self.trash.is_empty(),
+        };
+        self.bin_state.select(if empty { None } else { Some(0) });
+        self.set_input_mode(match bin {
+            Bin::Archive => InputMode::Archive,
+            Bin::Trash => InputMode::Trash,
+        });
+    }
+
+    pub fn open_bin(&self) -> Option<Bin> {
+        match self.input_mode {
+            InputMode::Archive => Some(Bin::Archive),
+            InputMode::Trash => Some(Bin::Trash),
+            _ => None,
+        }
+    }
+
+    fn bin_entries(&mut self, bin: Bin) -> &mut Vec<RemovedTodo> {
+        match bin {
+            Bin::Archive => &mut self.archive,
+            Bin::Trash => &mut self.trash,
+        }
+    }
+
+    pub fn select_next_bin_entry(&mut self, forward: bool) {
+        let Some(bin) = self.open_bin() else {
+            return;
+        };
+        let count = self.bin_entries(bin).len();
+        if count == 0 {
+            return;
+        }
+        let index = self.bin_state.selected().unwrap_or(0);
+        let index = if forward { (index + 1).min(count - 1) } else { index.saturating_sub(1) };
+        self.bin_state.select(Some(index));
+    }
+
+    // Takes the selected entry and the entries below it out of the open bin
+    fn take_selected_entries(&mut self) -> Vec<RemovedTodo> {
+        let (Some(bin), Some(index)) = (self.open_bin(), self.bin_state.selected()) else {
+            return Vec::new();
+        };
+        let entries = self.bin_entries(bin);
+        let Some(id) = entries.get(index).map(|e| e.todo.id) else {
+            return Vec::new();
+        };
+        let mut ids = bin_descendant_ids(entries, id);
+        ids.push(id);
+        let (taken, kept): (Vec<RemovedTodo>, Vec<RemovedTodo>) = std::mem::take(entries)
+            .into_iter()
+            .partition(|e| ids.contains(&e.todo.id));
+        *entries = kept;
+        let count = entries.len();
+        self.bin_state.select(if count == 0 { None } else { Some(index.min(count - 1)) });
+        taken
+    }
+
+    /// Puts the selected bin entry, with its subtasks, back in the todo list
+    pub fn restore_selected(&mut self) {
+        let Some(bin) = self.open_bin() else {
+            return;
+        };
+        let entries = self.take_selected_entries();
+        if entries.is_empty() {
+            return;
+        }
+        let restored: Vec<Todo> = entries.into_iter().map(|e| e.todo).collect();
+        let mut id_map = std::collections::HashMap::new();
+        for todo in &restored {
+            let id = if self.todos.iter().any(|t| t.id == todo.id) {
+                self.next_id += 1;
+                self.next_id - 1
+            } else {
+                todo.id
+            };
+            id_map.insert(todo.id, id);
+        }
+        let count = restored.len();
+        let mut index = self.todos.len();
+        let mut commands = Vec::new();
+        for mut todo in restored {
+            todo.id = id_map[&todo.id];
+            todo.parent_id = match todo.parent_id {
+                Some(parent) if id_map.contains_key(&parent) => Some(id_map[&parent]),
+                Some(parent) if self.todos.iter().any(|t| t.id == parent) => Some(parent),
+                _ => None,
+            };
+            commands.push(TodoCommand::Insert { index, todo, bin: Some(bin) });
+            index += 1;
+        }
+        self.execute(TodoCommand::Batch(commands));
+        self.sync_lists();
+        self.set_status(format!("Restored {} todo(s)", count));
+    }
+
+    /// Deletes the selected bin entry, with its subtasks, for good
+    pub fn purge_selected(&mut self) {
+        let count = self.take_selected_entries().len();
+        if count > 0 {
+            self.set_status(format!("Permanently deleted {} todo(s)", count));
+        }
+    }
+
+    pub fn close_bin_view(&mut self) {
+        self.set_input_mode(InputMode::Normal);
+    }
+
+    pub fn set_status(&mut self, message: impl Into<String>) {
+        self.status = Some(message.into());
+    }
+
+    pub fn clear_status(&mut self) {
+        self.status = None;
+    }
+
+    /// Opens the filter bar pre-filled with the active query
+    pub fn start_filter(&mut self) {
+        let query = self.filter.as_ref().map(|f| f.source().to_string()).unwrap_or_default();
+        self.input.set_text(query);
+        self.set_input_mode(InputMode::Filter);
+    }
+
+    /// Parses the filter bar; an empty query clears the filter
+    pub fn apply_filter_input(&mut self) {
+        let query = self.input.text().to_string();
+        match self.apply_filter(&query) {
+            Ok(()) => {
+                self.finish_input();
+                self.set_input_mode(InputMode::Normal);
+            }
+            Err(e) => self.set_status(format!("Invalid filter: {}", e)),
+        }
+    }
+
+    pub fn apply_filter(&mut self, query: &str) -> Result<()> {
+        self.filter = if query.trim().is_empty() {
+            None
+        } else {
+            Some(Filter::parse(query)?)
+        };
+        self.todo_list_state.select(Some(0));
+        self.clamp_selection();
+        Ok(())
+    }
+
+    pub fn clear_filter(&mut self) {
+        self.filter = None;
+        self.clamp_selection();
+    }
+
+    pub fn start_command(&mut self) {
+        self.clear_input();
+        self.set_input_mode(InputMode::Command);
+    }
+
+    /// Runs the command typed after `:` and reports the outcome
+    pub fn submit_command(&mut self) {
+        let line = self.input.text().to_string();
+        match command::execute(self, &line) {
+            Ok(message) => self.set_status(message),
+            Err(e) => self.set_status(format!("Error: {:#}", e)),
+        }
+        self.finish_input();
+        // Commands such as `report` open a screen of their own
+        if matches!(self.input_mode, InputMode::Command) {
+            self.set_input_mode(InputMode::Normal);
+        }
+    }
+
+    /// Appends todos from another source, giving them fresh ids so they never
+    /// collide with existing ones. Parent links inside the batch are kept.
+    pub fn import_todos(&mut self, mut todos: Vec<Todo>) -> usize {
+        self.workflow.normalize(&mut todos);
+        let mut id_map = std::collections::HashMap::new();
+        for todo in &todos {
+            id_map.insert(todo.id, self.next_id);
+            self.next_id += 1;
+        }
+        let count = todos.len();
+        let mut index = self.todos.len();
+        let list = self.current_list_name().to_string();
+        let commands = todos
+            .into_iter()
+            .map(|mut todo| {
+                todo.id = id_map[&todo.id];
+                todo.list = list.clone();
+                todo.parent_id = todo.parent_id.and_then(|parent| id_map.get(&parent).copied());
+                let command = TodoCommand::Insert { index, todo, bin: None };
+                index += 1;
+                command
+            })
+            .collect();
+        self.execute(TodoCommand::Batch(commands));
+        count
+    }
+
+    pub fn set_views(&mut self, views: Vec<SavedView>) {
+        self.views = views;
+    }
+
+    pub fn get_views(&self) -> &[SavedView] {
+        &self.views
+    }
+
+    pub fn start_save_view(&mut self) {
+        if self.filter.is_none() {
+            self.set_status("Nothing to save: no filter is active");
+            return;
+        }
+        self.clear_input();
+        self.set_input_mode(InputMode::ViewName);
+    }
+
+    /// Saves the active filter under the name typed into the input box
+    pub fn save_view_input(&mut self) {
+        let name = self.input.text().trim().to_string();
+        if let (false, Some(filter)) = (name.is_empty(), &self.filter) {
+            let view = SavedView { name: name.clone(), query: filter.source().to_string() };
+            match self.views.iter_mut().find(|v| v.name == name) {
+                Some(existing) => *existing = view,
+                None => self.views.push(view),
+            }
+            self.set_status(format!("Saved view '{}'", name));
+        }
+        self.clear_input();
+        self.set_input_mode(InputMode::Normal);
+    }
+
+    /// Applies the saved view after the one currently active
+    pub fn cycle_view(&mut self) {
+        if self.views.is_empty() {
+            self.set_status("No saved views");
+            return;
+        }
+        let current = self.filter.as_ref().and_then(|f| {
+            self.views.iter().position(|v| v.query == f.source())
+        });
+        let next = current.map(|i| (i + 1) % self.views.len()).unwrap_or(0);
+        let view = self.views[next].clone();
+        match self.apply_filter(&view.query) {
+            Ok(()) => self.set_status(format!("View: {}", view.name)),
+            Err(e) => self.set_status(format!("View '{}' is invalid: {}", view.name, e)),
+        }
+    }
+
+    pub fn cycle_priority(&mut self, id: usize) {
+        self.update_todo(id, |todo| todo.priority = Priority::cycle(todo.priority));
+    }
+
+    /// Moves the due date by `days`, starting from today if none is set
+    pub fn shift_due_date(&mut self, id: usize, days: i64) {
+        self.update_todo(id, |todo| {
+            let due = todo.due.unwrap_or_else(|| chrono::Local::now().date_naive());
+            todo.due = Some(due + chrono::Duration::days(days));
+        });
+    }
+
+    /// Applies a command to the list and records it for undo
+    fn execute(&mut self, command: TodoCommand) {
+        if matches!(&command, TodoCommand::Batch(commands) if commands.is_empty()) {
+            return;
+        }
+        command.apply(&mut self.todos);
+        self.sync_bins(&command);
+        self.rename_tabs(&command);
+        self.history.record(command);
+        self.renumber();
+        self.clamp_selection();
+    }
+
+    // Manual order is the order of `self.todos`; mirror it into `position`
+    // so it is saved explicitly
+    fn renumber(&mut self) {
+        for (position, todo) in self.todos.iter_mut().enumerate() {
+            todo.position = position;
+        }
+    }
+
+    /// Switches to the next sort mode, keeping the cursor on the same todo
+    pub fn cycle_sort(&mut self) {
+        let selected = self.selected_todo_id();
+        self.sort = self.sort.next();
+        if let Some(id) = selected {
+            self.select_todo(id);
+        }
+        self.set_status(format!("Sorted by {}", self.sort.label()));
+    }
+
+    pub fn set_sort_mode(&mut self, sort: SortMode) {
+        self.sort = sort;
+        self.clamp_selection();
+    }
+
+    pub fn get_sort_mode(&self) -> SortMode {
+        self.sort
+    }
+
+    /// Builds an `Update` command from an edit to a copy of the todo
+    fn update_command(&self, id: usize, edit: impl FnOnce(&mut Todo)) -> Option<TodoCommand> {
+        let before = self.todos.iter().find(|t| t.id == id)?.clone();
+        let mut after = before.clone();
+        edit(&mut after);
+        Some(TodoCommand::Update { before, after })
+    }
+
+    /// Commands that complete a todo, or reopen it in the initial state
+    fn toggle_commands(&mut self, id: usize) -> Vec<TodoCommand> {
+        let Some(done) = self.todos.iter().find(|t| t.id == id).map(|t| t.is_done()) else {
+            return Vec::new();
+        };
+        let state = if done { self.workflow.initial().to_string() } else { DONE_STATE.to_string() };
+        self.state_commands(id, &state)
+    }
+
+    /// Commands that move a todo to `state`. Completing a recurring todo also
+    /// inserts its next occurrence, which takes over the recurrence rule.
+    fn state_commands(&mut self, id: usize, state: &str) -> Vec<TodoCommand> {
+        let Some(todo) = self.todos.iter().find(|t| t.id == id) else {
+            return Vec::new();
+        };
+        let now = chrono::Local::now();
+        let next = match todo.is_done() || state != DONE_STATE {
+            true => None,
+            false => todo.next_occurrence(self.next_id, now, self.workflow.initial()),
+        };
+        let mut commands = Vec::new();
+        if let Some(next) = next {
+            self.next_id += 1;
+            let index = self.todos.len();
+            commands.extend(self.update_command(id, |todo| {
+                todo.set_state(state);
+                todo.completed_at = Some(now);
+                todo.recurrence = None;
+            }));
+            commands.push(TodoCommand::Insert { index, todo: next, bin: None });
+        } else {
+            commands.extend(self.update_command(id, |todo| todo.set_state(state)));
+        }
+        commands
+    }
+
+    fn update_todo(&mut self, id: usize, edit: impl FnOnce(&mut Todo)) {
+        if let Some(command) = self.update_command(id, edit) {
+            self.execute(command);
+        }
+    }
+
+    pub fn undo(&mut self) {
+        if let Some(command) = self.history.undo(&mut self.todos) {
+            self.sync_bins(&command);
+            self.rename_tabs(&command);
+            self.after_history_change();
+            self.set_status("Undone");
+        } else {
+            self.set_status("Nothing to undo");
+        }
+    }
+
+    pub fn redo(&mut self) {
+        if let Some(command) = self.history.redo(&mut self.todos) {
+            self.sync_bins(&command);
+            self.rename_tabs(&command);
+            self.after_history_change();
+            self.set_status("Redone");
+        } else {
+            self.set_status("Nothing to redo");
+        }
+    }
+
+    // A todo lives in exactly one place: todos `command` brings back leave
+    // their bin, and todos it takes away go to the bin it names, if any.
+    // Removals are recorded back to front, so they are added in reverse to
+    // keep list order in the bin.
+    fn sync_bins(&mut self, command: &TodoCommand) {
+        let todos = &self.todos;
+        self.archive.retain(|e| !todos.iter().any(|t| t.id == e.todo.id));
+        self.trash.retain(|e| !todos.iter().any(|t| t.id == e.todo.id));
+        for (todo, bin) in command.removed().into_iter().rev() {
+            let entries = self.bin_entries(bin);
+            if !entries.iter().any(|e| e.todo.id == todo.id) {
+                entries.push(RemovedTodo::new(todo));
+            }
+        }
+    }
+
+    // List tabs live outside `self.todos`, so renames carry them over here
+    fn rename_tabs(&mut self, command: &TodoCommand) {
+        match command {
+            TodoCommand::RenameList { old, new } => {
+                if let Some(index) = self.lists.iter().position(|l| l == old) {
+                    match self.lists.iter().any(|l| l == new) {
+                        true => {
+                            self.lists.remove(index);
+                        }
+                        false => self.lists[index] = new.clone(),
+                    }
+                }
+            }
+            TodoCommand::Batch(commands) => commands.iter().for_each(|c| self.rename_tabs(c)),
+            _ => {}
+        }
+    }
+
+    fn after_history_change(&mut self) {
+        // Never hand out an id that a redo could bring back
+        let max_id = self.todos.iter().map(|t| t.id).max().unwrap_or(0);
+        self.next_id = self.next_id.max(max_id + 1);
+        self.sync_lists();
+        self.renumber();
+        self.clamp_selection();
+    }
+
+    pub fn set_history(&mut self, history: History) {
+        self.history = history;
+    }
+
+    pub fn get_history(&self) -> &History {
+        &self.history
+    }
+
+    // Keep the cursor on a valid row after the list shrinks or grows
+    fn clamp_selection(&mut self) {
+        let count = self.visible_rows().len();
+        if count == 0 {
+            self.todo_list_state.select(None);
+        } else {
+            let index = self.todo_list_state.selected().unwrap_or(0);
+            self.todo_list_state.select(Some(index.min(count - 1)));
+        }
+    }
+
+    // Index of the neighbouring sibling in `self.todos`, in the given direction
+    fn sibling_index(&self, index: usize, forward: bool) -> Option<usize> {
+        let parent = self.todos[index].parent_id;
+        let list = &self.todos[index].list;
+        let is_sibling = |i: &usize| self.todos[*i].parent_id == parent && &self.todos[*i].list == list;
+        if forward {
+            (index + 1..self.todos.len()).find(is_sibling)
+        } else {
+            (0..index).rev().find(is_sibling)
+        }
     }
 
     pub fn cleanup(&mut self) -> Result<()> {
@@ -280,21 +1802,82 @@ impl App {
 
 impl TodoManager for App {
     fn add_todo(&mut self, title: String) {
-        let todo = Todo::new(self.next_id,title);
-        self.todos.push(todo);
+        let mut todo = Todo::from_input(self.next_id, &title);
+        todo.list = self.current_list_name().to_string();
+        todo.state = self.workflow.initial().to_string();
+        self.next_id += 1;
+        self.execute(TodoCommand::Insert { index: self.todos.len(), todo, bin: None });
+    }
+
+    fn add_subtask(&mut self, parent_id: usize, title: String) {
+        let mut todo = Todo::from_input(self.next_id, &title);
+        todo.parent_id = Some(parent_id);
+        todo.state = self.workflow.initial().to_string();
+        if let Some(parent) = self.todos.iter().find(|t| t.id == parent_id) {
+            todo.list = parent.list.clone();
+        }
+        let id = todo.id;
         self.next_id += 1;
+        // Expanding the parent is part of the command so undo collapses it again
+        let mut commands = vec![TodoCommand::Insert { index: self.todos.len(), todo, bin: None }];
+        if self.todos.iter().any(|t| t.id == parent_id && t.collapsed) {
+            commands.extend(self.update_command(parent_id, |parent| parent.collapsed = false));
+        }
+        self.execute(TodoCommand::Batch(commands));
+        self.select_todo(id);
     }
 
     fn remove_todo(&mut self, id: usize) {
-        self.todos.retain(|todo| todo.id != id);
+        let mut removed = descendant_ids(&self.todos, id);
+        removed.push(id);
+        // Remove from the back so the recorded indices stay valid on undo
+        let commands = self
+            .todos
+            .iter()
+            .enumerate()
+            .rev()
+            .filter(|(_, todo)| removed.contains(&todo.id))
+            .map(|(index, todo)| TodoCommand::Remove { index, todo: todo.clone(), bin: Some(Bin::Trash) })
+            .collect();
+        self.execute(TodoCommand::Batch(commands));
     }
 
     fn toggle_todo(&mut self,id:usize) {
-        if let Some(todo) = self.todos.iter_mut().find(|t| t.id == id) {
-            todo.toggle_complete();
+        let commands = self.toggle_commands(id);
+        self.execute(TodoCommand::Batch(commands));
+    }
+
+    // Reordering swaps a todo with its neighbouring sibling, so subtasks
+    // travel with their parent
+    fn move_todo_up(&mut self, id: usize) {
+        if self.sort != SortMode::Manual {
+            self.set_status("Switch to manual sort (o) to reorder");
+            return;
+        }
+        if let Some(index) = self.todos.iter().position(|t| t.id == id) {
+            if let Some(other) = self.sibling_index(index, false) {
+                let second = self.todos[other].id;
+                self.execute(TodoCommand::Swap { first: id, second });
+                self.select_todo(id);
+            }
+        }
+    }
+
+    fn move_todo_down(&mut self, id: usize) {
+        if self.sort != SortMode::Manual {
+            self.set_status("Switch to manual sort (o) to reorder");
+            return;
+        }
+        if let Some(index) = self.todos.iter().position(|t| t.id == id) {
+            if let Some(other) = self.sibling_index(index, true) {
+                let second = self.todos[other].id;
+                self.execute(TodoCommand::Swap { first: id, second });
+                self.select_todo(id);
+            }
         }
     }
+
     fn get_todos(&self) -> &Vec<Todo> {
         &self.todos
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,48 @@
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::state::todo::Todo;
+
+/// A todo taken out of the working list, kept so it can be restored
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RemovedTodo {
+    pub todo: Todo,
+    pub removed_at: DateTime<Local>,
+}
+
+impl RemovedTodo {
+    pub fn new(todo: Todo) -> Self {
+        Self { todo, removed_at: Local::now() }
+    }
+}
+
+/// Where removed todos go: completed ones are archived, deleted ones trashed
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Bin {
+    Archive,
+    Trash,
+}
+
+impl Bin {
+    pub fn title(&self) -> &'static str {
+        match self {
+            Bin::Archive => "Archive",
+            Bin::Trash => "Trash",
+        }
+    }
+}
+
+/// Ids of the entries below `id` in a bin, following the saved parent links
+pub fn bin_descendant_ids(entries: &[RemovedTodo], id: usize) -> Vec<usize> {
+    let mut ids = Vec::new();
+    let mut frontier = vec![id];
+    while let Some(parent) = frontier.pop() {
+        for entry in entries.iter().filter(|e| e.todo.parent_id == Some(parent)) {
+            if entry.todo.id != id && !ids.contains(&entry.todo.id) {
+                ids.push(entry.todo.id);
+                frontier.push(entry.todo.id);
+            }
+        }
+    }
+    ids
+}
@@ -0,0 +1,106 @@
+use anyhow::{bail, Context, Result};
+use std::path::Path;
+
+use crate::state::app::App;
+use crate::state::timelog::ReportRange;
+use crate::state::todo::{parse_date, TodoManager};
+use crate::utils::{ical, markdown, org, todotxt};
+
+/// Runs a `:` command line from the TUI and returns a message describing
+/// what happened.
+///
+/// - `import <format> <file>` merges todos from a file
+/// - `export <format> <file>` writes every todo to a file
+/// - `move <list>` moves the selected todo to another list
+/// - `list new <name>`, `list rm <name>`, `list rename <old> -> <new>`
+///   (`list rename <old> <new>` when the old name is a single word)
+/// - `state <name>` moves the selected todo to a workflow state
+/// - `pomodoro stop` ends the running Pomodoro
+/// - `report [<from> [<to>]]` opens the time report, `report csv <file>`
+///   exports it
+pub fn execute(app: &mut App, line: &str) -> Result<String> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    match words.as_slice() {
+        ["import", format, path @ ..] if !path.is_empty() => import(app, format, Path::new(&path.join(" "))),
+        ["export", format, path @ ..] if !path.is_empty() => export(app, format, Path::new(&path.join(" "))),
+        ["move", list @ ..] if !list.is_empty() => {
+            let list = list.join(" ");
+            let id = app.selected_todo_id().context("no todo selected")?;
+            app.move_to_list(id, &list)?;
+            Ok(format!("Moved to '{}'", list))
+        }
+        ["list", "new", name @ ..] if !name.is_empty() => {
+            let name = name.join(" ");
+            app.create_list(&name)?;
+            Ok(format!("Created list '{}'", name))
+        }
+        ["list", "rm", name @ ..] if !name.is_empty() => {
+            let name = name.join(" ");
+            app.remove_list(&name)?;
+            Ok(format!("Removed list '{}'", name))
+        }
+        ["list", "rename", names @ ..] if !names.is_empty() => {
+            let (old, new) = match names.iter().position(|w| *w == "->") {
+                Some(arrow) => (names[..arrow].join(" "), names[arrow + 1..].join(" ")),
+                None => (names[0].to_string(), names[1..].join(" ")),
+            };
+            if old.is_empty() || new.is_empty() {
+                bail!("usage: list rename <old> -> <new>");
+            }
+            app.rename_list(&old, &new)?;
+            Ok(format!("Renamed list '{}' to '{}'", old, new))
+        }
+        ["state", name @ ..] if !name.is_empty() => {
+            let name = name.join(" ");
+            let id = app.selected_todo_id().context("no todo selected")?;
+            let state = app.find_state(&name).with_context(|| format!("no workflow state named '{}'", name))?;
+            app.set_todo_state(id, &state);
+            Ok(format!("Moved to {}", state))
+        }
+        ["pomodoro", "stop"] => app.stop_pomodoro(),
+        ["report", "csv", path @ ..] if !path.is_empty() => app.export_time_report(Path::new(&path.join(" "))),
+        ["report"] => {
+            app.open_time_report(None);
+            Ok("Opened the time report".to_string())
+        }
+        ["report", from] | ["report", from, _] => {
+            let from = parse_date(from).with_context(|| format!("invalid date '{}'", from))?;
+            let to = match words.get(2) {
+                Some(to) => parse_date(to).with_context(|| format!("invalid date '{}'", to))?,
+                None => chrono::Local::now().date_naive(),
+            };
+            if to < from {
+                bail!("the report range ends before it starts");
+            }
+            app.open_time_report(Some(ReportRange::Custom(from, to)));
+            Ok("Opened the time report".to_string())
+        }
+        [] => bail!("empty command"),
+        _ => bail!("unknown command '{}'", line.trim()),
+    }
+}
+
+/// Formats: `todotxt`, `md` (GitHub task list), `org`, `ics` (VTODO)
+pub fn import(app: &mut App, format: &str, path: &Path) -> Result<String> {
+    let todos = match format {
+        "todotxt" | "todo.txt" => todotxt::read(path)?,
+        "md" | "markdown" => markdown::read(path)?,
+        "org" => org::read(path)?,
+        "ics" | "ical" => ical::read(path, app.workflow())?,
+        _ => bail!("unknown import format '{}'", format),
+    };
+    let count = app.import_todos(todos);
+    Ok(format!("Imported {} todo(s) from {}", count, path.display()))
+}
+
+pub fn export(app: &App, format: &str, path: &Path) -> Result<String> {
+    let todos = app.get_todos();
+    match format {
+        "todotxt" | "todo.txt" => todotxt::write(todos, path)?,
+        "md" | "markdown" => markdown::write(todos, path)?,
+        "org" => org::write(todos, path)?,
+        "ics" | "ical" => ical::write(todos, path, app.workflow())?,
+        _ => bail!("unknown export format '{}'", format),
+    }
+    Ok(format!("Exported {} todo(s) to {}", todos.len(), path.display()))
+}
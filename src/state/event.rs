@@ -14,6 +14,7 @@ use crate::utils::art::get_album_art;
 use std::path::Path;
 use crate::state::app::{ InputMode};
 use crate::state::todo::{Todo, TodoManager};
+use crate::state::archive::Bin;
 
 pub struct EventHandler;
 
@@ -25,6 +26,10 @@ impl EventHandler {
                 Event::Key(key) if key.kind == KeyEventKind::Press => {
                     Self::handle_key_event(app_state, key)
                 }
+                Event::Paste(text) if app_state.get_input_mode().is_text_entry() => {
+                    app_state.input_editor().insert_str(&text);
+                    Ok(false)
+                }
                 _ => Ok(false),
             }
         } else {
@@ -36,20 +41,225 @@ impl EventHandler {
         use crate::state::app::InputMode;
 
         match (key.code, app_state.get_input_mode()) {
-            (KeyCode::Char('q'), _) => return Ok(true),  // Exit the application
+            (KeyCode::Char('q'), mode) if !mode.is_text_entry() => return Ok(true),  // Exit the application
 
             (KeyCode::Char('i'), InputMode::Normal) => {
-                app_state.set_input_mode(InputMode::Editing);
+                app_state.start_input(None);
+            }
+
+            (KeyCode::Char('e'), InputMode::Normal) => {
+                if let Some(id) = app_state.selected_todo_id() {
+                    app_state.start_edit(id);
+                }
+            }
+
+            (KeyCode::Char('a'), InputMode::Normal) => {
+                if let Some(id) = app_state.selected_todo_id() {
+                    app_state.start_input(Some(id));
+                }
             }
 
             (KeyCode::Enter, InputMode::Editing) => {
-                if !app_state.get_input().is_empty() {
-                    app_state.add_todo(app_state.get_input().to_string());
-                    app_state.clear_input();
-                    app_state.set_input_mode(InputMode::Normal);
+                app_state.submit_input();
+            }
+
+            (KeyCode::Esc, InputMode::Editing) => {
+                app_state.cancel_input();
+            }
+
+            // Filter bar and saved views
+            (KeyCode::Char('/'), InputMode::Normal) => {
+                app_state.start_filter();
+            }
+
+            (KeyCode::Enter, InputMode::Filter) => {
+                app_state.apply_filter_input();
+            }
+
+            (KeyCode::Esc, InputMode::Normal) => {
+                app_state.clear_filter();
+                app_state.clear_status();
+            }
+
+            (KeyCode::Char('v'), InputMode::Normal) => {
+                app_state.cycle_view();
+            }
+
+            (KeyCode::Char('V'), InputMode::Normal) => {
+                app_state.start_save_view();
+            }
+
+            (KeyCode::Enter, InputMode::ViewName) => {
+                app_state.save_view_input();
+            }
+
+            (KeyCode::Char('n'), InputMode::Normal) => {
+                if let Some(id) = app_state.selected_todo_id() {
+                    app_state.request_notes_edit(id);
                 }
             }
 
+            (KeyCode::Char('N'), InputMode::Normal) => {
+                app_state.toggle_notes_pane();
+            }
+
+            (KeyCode::Char('o'), InputMode::Normal) => {
+                app_state.cycle_sort();
+            }
+
+            (KeyCode::Char('x'), InputMode::Normal) => {
+                app_state.sweep_completed();
+            }
+
+            (KeyCode::Char('A'), InputMode::Normal) => {
+                app_state.open_bin_view(Bin::Archive);
+            }
+
+            (KeyCode::Char('T'), InputMode::Normal) => {
+                app_state.open_bin_view(Bin::Trash);
+            }
+
+            (KeyCode::Up | KeyCode::Char('k'), InputMode::Archive | InputMode::Trash) => {
+                app_state.select_next_bin_entry(false);
+            }
+
+            (KeyCode::Down | KeyCode::Char('j'), InputMode::Archive | InputMode::Trash) => {
+                app_state.select_next_bin_entry(true);
+            }
+
+            (KeyCode::Char('r'), InputMode::Archive | InputMode::Trash) => {
+                app_state.restore_selected();
+            }
+
+            (KeyCode::Char('D') | KeyCode::Delete, InputMode::Archive | InputMode::Trash) => {
+                app_state.purge_selected();
+            }
+
+            (KeyCode::Esc, InputMode::Archive | InputMode::Trash) => {
+                app_state.close_bin_view();
+            }
+
+            (KeyCode::Char('s'), InputMode::Normal) => {
+                app_state.set_input_mode(InputMode::Stats);
+            }
+
+            (KeyCode::Char('s') | KeyCode::Esc, InputMode::Stats) => {
+                app_state.set_input_mode(InputMode::Normal);
+            }
+
+            (KeyCode::Char('w'), InputMode::Normal) => {
+                if let Some(id) = app_state.selected_todo_id() {
+                    app_state.toggle_timer(id);
+                }
+            }
+
+            (KeyCode::Char('W'), InputMode::Normal) => {
+                app_state.open_time_report(None);
+            }
+
+            (KeyCode::Tab, InputMode::TimeReport) => {
+                app_state.cycle_report_range();
+            }
+
+            (KeyCode::Char('W') | KeyCode::Esc, InputMode::TimeReport) => {
+                app_state.set_input_mode(InputMode::Normal);
+            }
+
+            (KeyCode::Char('B'), InputMode::Normal) => {
+                app_state.open_board();
+            }
+
+            (KeyCode::Left | KeyCode::Char('h'), InputMode::Board) => {
+                app_state.move_board_selection(-1, 0);
+            }
+
+            (KeyCode::Right | KeyCode::Char('l'), InputMode::Board) => {
+                app_state.move_board_selection(1, 0);
+            }
+
+            (KeyCode::Up | KeyCode::Char('k'), InputMode::Board) => {
+                app_state.move_board_selection(0, -1);
+            }
+
+            (KeyCode::Down | KeyCode::Char('j'), InputMode::Board) => {
+                app_state.move_board_selection(0, 1);
+            }
+
+            (KeyCode::Char('H') | KeyCode::Char('<'), InputMode::Board) => {
+                app_state.move_card(-1);
+            }
+
+            (KeyCode::Char('L') | KeyCode::Char('>'), InputMode::Board) => {
+                app_state.move_card(1);
+            }
+
+            (KeyCode::Char('B') | KeyCode::Esc, InputMode::Board) => {
+                app_state.close_board();
+            }
+
+            (KeyCode::Char('P'), InputMode::Normal) => {
+                if let Some(id) = app_state.selected_todo_id() {
+                    app_state.toggle_pomodoro(id);
+                }
+            }
+
+            (KeyCode::Enter | KeyCode::Esc, InputMode::Reminder) => {
+                app_state.dismiss_reminder();
+            }
+
+            (KeyCode::Char('z'), InputMode::Reminder) => {
+                app_state.snooze_reminder();
+            }
+
+            (KeyCode::Char('m'), InputMode::Conflict) => {
+                app_state.resolve_conflict(true);
+            }
+
+            (KeyCode::Char('t'), InputMode::Conflict) => {
+                app_state.resolve_conflict(false);
+            }
+
+            (KeyCode::Tab, InputMode::Normal) => {
+                app_state.cycle_list(true);
+            }
+
+            (KeyCode::BackTab, InputMode::Normal) => {
+                app_state.cycle_list(false);
+            }
+
+            (KeyCode::Char('m'), InputMode::Normal) => {
+                if app_state.selected_todo_id().is_some() {
+                    app_state.start_command();
+                    app_state.input_editor().set_text("move ");
+                }
+            }
+
+            // Command line
+            (KeyCode::Char(':'), InputMode::Normal) => {
+                app_state.start_command();
+            }
+
+            (KeyCode::Enter, InputMode::Command) => {
+                app_state.submit_command();
+            }
+
+            (KeyCode::Esc, InputMode::Filter | InputMode::ViewName | InputMode::Command) => {
+                app_state.cancel_input();
+            }
+
+            // Completing a parent with open subtasks
+            (KeyCode::Char('y'), InputMode::Confirm) => {
+                app_state.resolve_pending_complete(true);
+            }
+
+            (KeyCode::Char('n'), InputMode::Confirm) => {
+                app_state.resolve_pending_complete(false);
+            }
+
+            (KeyCode::Esc, InputMode::Confirm) => {
+                app_state.cancel_pending_complete();
+            }
+
             (KeyCode::Char('b'), InputMode::Normal|InputMode::Player) => {
                 if app_state.music_browser.is_none() {
                     if let Err(e) = app_state.show_music_browser() {
@@ -146,18 +356,135 @@ impl EventHandler {
             }
 
             // Input handling
-            (KeyCode::Char(c), InputMode::Editing) => {
+            (KeyCode::Char('w'), mode) if mode.is_text_entry() && key.modifiers.contains(KeyModifiers::CONTROL) => {
+                app_state.input_editor().delete_word_before();
+            }
+
+            (KeyCode::Char('u'), mode) if mode.is_text_entry() && key.modifiers.contains(KeyModifiers::CONTROL) => {
+                app_state.input_editor().delete_to_start();
+            }
+
+            (KeyCode::Char('a'), mode) if mode.is_text_entry() && key.modifiers.contains(KeyModifiers::CONTROL) => {
+                app_state.input_editor().move_home();
+            }
+
+            (KeyCode::Char('e'), mode) if mode.is_text_entry() && key.modifiers.contains(KeyModifiers::CONTROL) => {
+                app_state.input_editor().move_end();
+            }
+
+            (KeyCode::Char(c), mode) if mode.is_text_entry() && !key.modifiers.contains(KeyModifiers::CONTROL) => {
                 app_state.push_to_input(c);
             }
 
-            (KeyCode::Backspace, InputMode::Editing) => {
+            (KeyCode::Backspace, mode) if mode.is_text_entry() => {
                 app_state.pop_from_input();
             }
 
-            // Todo toggle
+            (KeyCode::Delete, mode) if mode.is_text_entry() => {
+                app_state.input_editor().delete();
+            }
+
+            (KeyCode::Left, mode) if mode.is_text_entry() => {
+                app_state.input_editor().move_left();
+            }
+
+            (KeyCode::Right, mode) if mode.is_text_entry() => {
+                app_state.input_editor().move_right();
+            }
+
+            (KeyCode::Home, mode) if mode.is_text_entry() => {
+                app_state.input_editor().move_home();
+            }
+
+            (KeyCode::End, mode) if mode.is_text_entry() => {
+                app_state.input_editor().move_end();
+            }
+
+            (KeyCode::Up, mode) if mode.is_text_entry() => {
+                app_state.input_editor().history_previous();
+            }
+
+            (KeyCode::Down, mode) if mode.is_text_entry() => {
+                app_state.input_editor().history_next();
+            }
+
+            // Todo selection
+            (KeyCode::Up | KeyCode::Char('k'), InputMode::Normal) => {
+                app_state.select_previous_todo();
+            }
+
+            (KeyCode::Down | KeyCode::Char('j'), InputMode::Normal) => {
+                app_state.select_next_todo();
+            }
+
+            // Actions on the selected todo
             (KeyCode::Char('t'), InputMode::Normal) => {
-                if let Some(first_todo) = app_state.get_todos().first() {
-                    app_state.toggle_todo(first_todo.id);
+                if let Some(id) = app_state.selected_todo_id() {
+                    app_state.request_toggle(id);
+                }
+            }
+
+            // Subtask tree
+            (KeyCode::Left | KeyCode::Char('h'), InputMode::Normal) => {
+                if let Some(id) = app_state.selected_todo_id() {
+                    app_state.set_collapsed(id, true);
+                }
+            }
+
+            (KeyCode::Right | KeyCode::Char('l'), InputMode::Normal) => {
+                if let Some(id) = app_state.selected_todo_id() {
+                    app_state.set_collapsed(id, false);
+                }
+            }
+
+            (KeyCode::Char(' '), InputMode::Normal) => {
+                if let Some(id) = app_state.selected_todo_id() {
+                    app_state.toggle_collapsed(id);
+                }
+            }
+
+            (KeyCode::Char('d'), InputMode::Normal) => {
+                if let Some(id) = app_state.selected_todo_id() {
+                    app_state.remove_todo(id);
+                }
+            }
+
+            (KeyCode::Char('p'), InputMode::Normal) => {
+                if let Some(id) = app_state.selected_todo_id() {
+                    app_state.cycle_priority(id);
+                }
+            }
+
+            (KeyCode::Char('>'), InputMode::Normal) => {
+                if let Some(id) = app_state.selected_todo_id() {
+                    app_state.shift_due_date(id, 1);
+                }
+            }
+
+            (KeyCode::Char('<'), InputMode::Normal) => {
+                if let Some(id) = app_state.selected_todo_id() {
+                    app_state.shift_due_date(id, -1);
+                }
+            }
+
+            // History
+            (KeyCode::Char('u'), InputMode::Normal) => {
+                app_state.undo();
+            }
+
+            (KeyCode::Char('r'), InputMode::Normal) if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                app_state.redo();
+            }
+
+            (KeyCode::Char('K'), InputMode::Normal) => {
+                if let Some(id) = app_state.selected_todo_id() {
+                    app_state.move_todo_up(id);
+                }
+            }
+
+            (KeyCode::Char('J'), InputMode::Normal) => {
+                if let Some(id) = app_state.selected_todo_id() {
+                    app_state.move_todo_down(id);
                 }
             }
 
@@ -0,0 +1,165 @@
+use anyhow::{bail, Result};
+use chrono::{Duration, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+use crate::state::todo::{Priority, Todo};
+
+/// A parsed filter query such as `+work @home -done due<7d priority>=high`.
+///
+/// Terms are and-ed together:
+/// - `+name` matches the project, `@name` matches a tag
+/// - `done` matches completed todos
+/// - `state:in_progress` matches a workflow state, with `_` for spaces
+/// - `due<7d`, `due<=today`, `due>2025-01-31` compare the due date
+/// - `priority>=high` compares the priority (`none`, `low`, `med`, `high`)
+/// - any other word matches the title case-insensitively
+/// - a leading `-` negates a term
+#[derive(Clone, Debug)]
+pub struct Filter {
+    source: String,
+    terms: Vec<Term>,
+}
+
+#[derive(Clone, Debug)]
+struct Term {
+    negated: bool,
+    kind: TermKind,
+}
+
+#[derive(Clone, Debug)]
+enum TermKind {
+    Project(String),
+    Tag(String),
+    Done,
+    State(String),
+    Due(Comparison, NaiveDate),
+    Priority(Comparison, Option<Priority>),
+    Text(String),
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Comparison {
+    Lt,
+    Le,
+    Eq,
+    Ge,
+    Gt,
+}
+
+impl Comparison {
+    fn holds<T: PartialOrd>(self, left: T, right: T) -> bool {
+        match self {
+            Comparison::Lt => left < right,
+            Comparison::Le => left <= right,
+            Comparison::Eq => left == right,
+            Comparison::Ge => left >= right,
+            Comparison::Gt => left > right,
+        }
+    }
+}
+
+/// A filter saved under a name so it can be recalled later
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SavedView {
+    pub name: String,
+    pub query: String,
+}
+
+impl Filter {
+    pub fn parse(query: &str) -> Result<Filter> {
+        let mut terms = Vec::new();
+        for word in query.split_whitespace() {
+            let (negated, word) = match word.strip_prefix('-') {
+                Some(rest) if !rest.is_empty() => (true, rest),
+                _ => (false, word),
+            };
+            terms.push(Term { negated, kind: Self::parse_term(word)? });
+        }
+        Ok(Filter { source: query.trim().to_string(), terms })
+    }
+
+    fn parse_term(word: &str) -> Result<TermKind> {
+        if let Some(project) = word.strip_prefix('+') {
+            return Ok(TermKind::Project(project.to_lowercase()));
+        }
+        if let Some(tag) = word.strip_prefix('@') {
+            return Ok(TermKind::Tag(tag.to_lowercase()));
+        }
+        if word.eq_ignore_ascii_case("done") {
+            return Ok(TermKind::Done);
+        }
+        if let Some(state) = word.strip_prefix("state:").filter(|s| !s.is_empty()) {
+            return Ok(TermKind::State(state.replace('_', " ").to_lowercase()));
+        }
+        if let Some(rest) = word.strip_prefix("due") {
+            if let Some((comparison, value)) = Self::split_comparison(rest) {
+                let today = Local::now().date_naive();
+                let date = match value {
+                    "today" => today,
+                    _ => match value.strip_suffix('d').and_then(|n| n.parse::<i64>().ok()) {
+                        Some(days) => today + Duration::days(days),
+                        None => match NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+                            Ok(date) => date,
+                            Err(_) => bail!("invalid due date '{}'", value),
+                        },
+                    },
+                };
+                return Ok(TermKind::Due(comparison, date));
+            }
+        }
+        if let Some(rest) = word.strip_prefix("priority") {
+            if let Some((comparison, value)) = Self::split_comparison(rest) {
+                let priority = match value {
+                    "none" => None,
+                    _ => match Priority::parse(value) {
+                        Some(priority) => Some(priority),
+                        None => bail!("invalid priority '{}'", value),
+                    },
+                };
+                return Ok(TermKind::Priority(comparison, priority));
+            }
+        }
+        Ok(TermKind::Text(word.to_lowercase()))
+    }
+
+    fn split_comparison(rest: &str) -> Option<(Comparison, &str)> {
+        for (prefix, comparison) in [
+            ("<=", Comparison::Le),
+            (">=", Comparison::Ge),
+            ("<", Comparison::Lt),
+            (">", Comparison::Gt),
+            ("=", Comparison::Eq),
+            (":", Comparison::Eq),
+        ] {
+            if let Some(value) = rest.strip_prefix(prefix) {
+                return Some((comparison, value));
+            }
+        }
+        None
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn matches(&self, todo: &Todo) -> bool {
+        self.terms.iter().all(|term| term.matches(todo) != term.negated)
+    }
+}
+
+impl Term {
+    fn matches(&self, todo: &Todo) -> bool {
+        match &self.kind {
+            TermKind::Project(project) => todo
+                .project
+                .as_ref()
+                .is_some_and(|p| p.to_lowercase() == *project),
+            TermKind::Tag(tag) => todo.tags.iter().any(|t| t.to_lowercase() == *tag),
+            TermKind::Done => todo.is_done(),
+            TermKind::State(state) => todo.state.to_lowercase() == *state,
+            TermKind::Due(comparison, date) => todo.due.is_some_and(|due| comparison.holds(due, *date)),
+            TermKind::Priority(comparison, priority) => comparison.holds(todo.priority, *priority),
+            TermKind::Text(text) => todo.title.to_lowercase().contains(text.as_str()),
+        }
+    }
+}
@@ -0,0 +1,165 @@
+use std::collections::{HashSet, VecDeque};
+
+use serde::{Deserialize, Serialize};
+
+use crate::state::archive::Bin;
+use crate::state::todo::Todo;
+
+/// A reversible change to the todo list. Every mutation on `App` is
+/// expressed as one of these so it can be undone by applying its inverse.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub enum TodoCommand {
+    /// `bin` is where a restored todo came from
+    Insert {
+        index: usize,
+        todo: Todo,
+        #[serde(default)]
+        bin: Option<Bin>,
+    },
+    /// `bin` is where the removed todo goes, if it is kept at all
+    Remove {
+        index: usize,
+        todo: Todo,
+        #[serde(default)]
+        bin: Option<Bin>,
+    },
+    Update { before: Todo, after: Todo },
+    Swap { first: usize, second: usize },
+    /// Moves every todo of list `old` to `new`; the tab follows in `App`
+    RenameList { old: String, new: String },
+    Batch(Vec<TodoCommand>),
+}
+
+impl TodoCommand {
+    pub fn apply(&self, todos: &mut Vec<Todo>) {
+        match self {
+            TodoCommand::Insert { index, todo, .. } => {
+                todos.insert((*index).min(todos.len()), todo.clone());
+            }
+            TodoCommand::Remove { index, todo, .. } => {
+                // Fall back to a lookup by id if the list has drifted
+                let position = match todos.get(*index) {
+                    Some(t) if t.id == todo.id => Some(*index),
+                    _ => todos.iter().position(|t| t.id == todo.id),
+                };
+                if let Some(position) = position {
+                    todos.remove(position);
+                }
+            }
+            TodoCommand::Update { after, .. } => {
+                if let Some(todo) = todos.iter_mut().find(|t| t.id == after.id) {
+                    *todo = after.clone();
+                }
+            }
+            TodoCommand::Swap { first, second } => {
+                let a = todos.iter().position(|t| t.id == *first);
+                let b = todos.iter().position(|t| t.id == *second);
+                if let (Some(a), Some(b)) = (a, b) {
+                    todos.swap(a, b);
+                }
+            }
+            TodoCommand::RenameList { old, new } => {
+                for todo in todos.iter_mut().filter(|t| t.list == *old) {
+                    todo.list = new.clone();
+                }
+            }
+            TodoCommand::Batch(commands) => {
+                for command in commands {
+                    command.apply(todos);
+                }
+            }
+        }
+    }
+
+    /// Ids of the todos this command touches
+    pub fn ids(&self) -> Vec<usize> {
+        match self {
+            TodoCommand::Insert { todo, .. } | TodoCommand::Remove { todo, .. } => vec![todo.id],
+            TodoCommand::Update { after, .. } => vec![after.id],
+            TodoCommand::Swap { first, second } => vec![*first, *second],
+            TodoCommand::RenameList { .. } => Vec::new(),
+            TodoCommand::Batch(commands) => commands.iter().flat_map(|c| c.ids()).collect(),
+        }
+    }
+
+    /// The todos this command takes out of the list and keeps in a bin
+    pub fn removed(&self) -> Vec<(Todo, Bin)> {
+        match self {
+            TodoCommand::Remove { todo, bin: Some(bin), .. } => vec![(todo.clone(), *bin)],
+            TodoCommand::Batch(commands) => commands.iter().flat_map(|c| c.removed()).collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    pub fn inverse(&self) -> TodoCommand {
+        match self {
+            TodoCommand::Insert { index, todo, bin } => TodoCommand::Remove {
+                index: *index,
+                todo: todo.clone(),
+                bin: *bin,
+            },
+            TodoCommand::Remove { index, todo, bin } => TodoCommand::Insert {
+                index: *index,
+                todo: todo.clone(),
+                bin: *bin,
+            },
+            TodoCommand::Update { before, after } => TodoCommand::Update {
+                before: after.clone(),
+                after: before.clone(),
+            },
+            TodoCommand::Swap { first, second } => TodoCommand::Swap { first: *first, second: *second },
+            TodoCommand::RenameList { old, new } => TodoCommand::RenameList {
+                old: new.clone(),
+                new: old.clone(),
+            },
+            TodoCommand::Batch(commands) => {
+                TodoCommand::Batch(commands.iter().rev().map(|c| c.inverse()).collect())
+            }
+        }
+    }
+}
+
+/// Bounded undo/redo stacks of `TodoCommand`s
+#[derive(Serialize, Deserialize, Default)]
+pub struct History {
+    undo: VecDeque<TodoCommand>,
+    redo: Vec<TodoCommand>,
+}
+
+impl History {
+    pub const LIMIT: usize = 100;
+
+    /// Records a command that has already been applied
+    pub fn record(&mut self, command: TodoCommand) {
+        self.undo.push_back(command);
+        while self.undo.len() > Self::LIMIT {
+            self.undo.pop_front();
+        }
+        self.redo.clear();
+    }
+
+    /// Drops the commands touching any of `ids`, which changed outside the
+    /// app; their snapshots would undo or redo over those changes
+    pub fn forget(&mut self, ids: &HashSet<usize>) {
+        let keep = |command: &TodoCommand| !command.ids().iter().any(|id| ids.contains(id));
+        self.undo.retain(keep);
+        self.redo.retain(keep);
+    }
+
+    /// Reverts the last command and returns what was applied to do so
+    pub fn undo(&mut self, todos: &mut Vec<Todo>) -> Option<TodoCommand> {
+        let command = self.undo.pop_back()?;
+        let inverse = command.inverse();
+        inverse.apply(todos);
+        self.redo.push(command);
+        Some(inverse)
+    }
+
+    /// Re-applies the last undone command and returns it
+    pub fn redo(&mut self, todos: &mut Vec<Todo>) -> Option<TodoCommand> {
+        let command = self.redo.pop()?;
+        command.apply(todos);
+        self.undo.push_back(command.clone());
+        Some(command)
+    }
+}
@@ -0,0 +1,171 @@
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// Single-line text input with a grapheme-aware cursor and a recall history
+#[derive(Default)]
+pub struct LineEditor {
+    text: String,
+    /// Cursor position in graphemes
+    cursor: usize,
+    history: Vec<String>,
+    /// Position while browsing the history with Up/Down
+    history_index: Option<usize>,
+    /// What was typed before browsing started
+    draft: String,
+}
+
+impl LineEditor {
+    pub const HISTORY_LIMIT: usize = 100;
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    /// Replaces the text and puts the cursor at the end
+    pub fn set_text(&mut self, text: impl Into<String>) {
+        self.text = text.into();
+        self.cursor = self.grapheme_count();
+        self.history_index = None;
+    }
+
+    pub fn clear(&mut self) {
+        self.set_text(String::new());
+    }
+
+    pub fn insert_char(&mut self, c: char) {
+        let at = self.byte_offset(self.cursor);
+        self.text.insert(at, c);
+        // A combining character may merge into the previous grapheme
+        self.cursor = self.text[..at + c.len_utf8()].graphemes(true).count();
+    }
+
+    /// Inserts pasted text, flattening line breaks into spaces
+    pub fn insert_str(&mut self, text: &str) {
+        let flat: String = text
+            .chars()
+            .map(|c| if c == '\n' || c == '\r' || c == '\t' { ' ' } else { c })
+            .collect();
+        let at = self.byte_offset(self.cursor);
+        self.text.insert_str(at, &flat);
+        self.cursor = self.text[..at + flat.len()].graphemes(true).count();
+    }
+
+    pub fn backspace(&mut self) {
+        if self.cursor > 0 {
+            let start = self.byte_offset(self.cursor - 1);
+            let end = self.byte_offset(self.cursor);
+            self.text.replace_range(start..end, "");
+            self.cursor -= 1;
+        }
+    }
+
+    pub fn delete(&mut self) {
+        if self.cursor < self.grapheme_count() {
+            let start = self.byte_offset(self.cursor);
+            let end = self.byte_offset(self.cursor + 1);
+            self.text.replace_range(start..end, "");
+        }
+    }
+
+    pub fn move_left(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+
+    pub fn move_right(&mut self) {
+        self.cursor = (self.cursor + 1).min(self.grapheme_count());
+    }
+
+    pub fn move_home(&mut self) {
+        self.cursor = 0;
+    }
+
+    pub fn move_end(&mut self) {
+        self.cursor = self.grapheme_count();
+    }
+
+    /// Ctrl-W: deletes back to the start of the previous word
+    pub fn delete_word_before(&mut self) {
+        let graphemes: Vec<&str> = self.text.graphemes(true).collect();
+        let mut start = self.cursor;
+        while start > 0 && graphemes[start - 1].trim().is_empty() {
+            start -= 1;
+        }
+        while start > 0 && !graphemes[start - 1].trim().is_empty() {
+            start -= 1;
+        }
+        let (from, to) = (self.byte_offset(start), self.byte_offset(self.cursor));
+        self.text.replace_range(from..to, "");
+        self.cursor = start;
+    }
+
+    /// Ctrl-U: deletes everything before the cursor
+    pub fn delete_to_start(&mut self) {
+        let to = self.byte_offset(self.cursor);
+        self.text.replace_range(..to, "");
+        self.cursor = 0;
+    }
+
+    /// Remembers a submitted line for later recall
+    pub fn push_history(&mut self, entry: &str) {
+        if entry.trim().is_empty() || self.history.last().is_some_and(|last| last == entry) {
+            return;
+        }
+        self.history.push(entry.to_string());
+        if self.history.len() > Self::HISTORY_LIMIT {
+            self.history.remove(0);
+        }
+    }
+
+    pub fn history_previous(&mut self) {
+        if self.history.is_empty() {
+            return;
+        }
+        let index = match self.history_index {
+            Some(index) => index.saturating_sub(1),
+            None => {
+                self.draft = self.text.clone();
+                self.history.len() - 1
+            }
+        };
+        self.show_history(Some(index));
+    }
+
+    pub fn history_next(&mut self) {
+        match self.history_index {
+            Some(index) if index + 1 < self.history.len() => self.show_history(Some(index + 1)),
+            Some(_) => self.show_history(None),
+            None => {}
+        }
+    }
+
+    fn show_history(&mut self, index: Option<usize>) {
+        let text = match index {
+            Some(index) => self.history[index].clone(),
+            None => std::mem::take(&mut self.draft),
+        };
+        self.set_text(text);
+        self.history_index = index;
+    }
+
+    /// Display width of the text before the cursor, for placing the
+    /// terminal cursor
+    pub fn cursor_column(&self) -> usize {
+        self.text[..self.byte_offset(self.cursor)].width()
+    }
+
+    fn grapheme_count(&self) -> usize {
+        self.text.graphemes(true).count()
+    }
+
+    fn byte_offset(&self, grapheme: usize) -> usize {
+        self.text
+            .grapheme_indices(true)
+            .nth(grapheme)
+            .map(|(offset, _)| offset)
+            .unwrap_or(self.text.len())
+    }
+}
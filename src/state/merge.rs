@@ -0,0 +1,103 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::state::todo::Todo;
+
+/// A todo changed both here and in the store since the last sync. `None`
+/// means that side deleted it.
+#[derive(Clone, Debug)]
+pub struct Conflict {
+    pub ours: Option<Todo>,
+    pub theirs: Option<Todo>,
+}
+
+impl Conflict {
+    pub fn id(&self) -> usize {
+        self.ours.as_ref().or(self.theirs.as_ref()).map(|t| t.id).unwrap_or(0)
+    }
+}
+
+/// Three-way merge of two edited copies of the todo list, keyed by id.
+///
+/// A todo changed on one side only takes that side's version, deletions
+/// included. Todos changed on both sides are returned as conflicts and keep
+/// our version in the result until resolved. Todos both sides added under
+/// the same id are both kept, theirs under a fresh id from `next_id`.
+pub fn merge(base: &[Todo], ours: &[Todo], theirs: &[Todo], next_id: &mut usize) -> (Vec<Todo>, Vec<Conflict>) {
+    let base: HashMap<usize, &Todo> = base.iter().map(|t| (t.id, t)).collect();
+    let their_map: HashMap<usize, &Todo> = theirs.iter().map(|t| (t.id, t)).collect();
+    let our_ids: Vec<usize> = ours.iter().map(|t| t.id).collect();
+
+    let mut merged = Vec::new();
+    let mut conflicts = Vec::new();
+    for todo in ours {
+        let original = base.get(&todo.id).copied();
+        match (original, their_map.get(&todo.id).copied()) {
+            // Unchanged here: theirs wins, including their deletion
+            (Some(original), their) if same(original, todo) => merged.extend(their.cloned()),
+            // Changed here only, or both made the same change
+            (Some(original), Some(their)) if same(original, their) || same(todo, their) => merged.push(todo.clone()),
+            // Changed here, deleted there
+            (Some(_), None) => {
+                conflicts.push(Conflict { ours: Some(todo.clone()), theirs: None });
+                merged.push(todo.clone());
+            }
+            // Changed differently on both sides
+            (Some(_), Some(their)) => {
+                conflicts.push(Conflict { ours: Some(todo.clone()), theirs: Some(their.clone()) });
+                merged.push(todo.clone());
+            }
+            // New here
+            (None, _) => merged.push(todo.clone()),
+        }
+    }
+
+    let mut renamed = HashMap::new();
+    for todo in theirs {
+        if our_ids.contains(&todo.id) {
+            // New on both sides under the same id: two different todos
+            if !base.contains_key(&todo.id) && !same(todo, ours.iter().find(|t| t.id == todo.id).unwrap()) {
+                let mut todo = todo.clone();
+                renamed.insert(todo.id, *next_id);
+                todo.id = *next_id;
+                *next_id += 1;
+                merged.push(todo);
+            }
+            continue;
+        }
+        match base.get(&todo.id).copied() {
+            // Deleted here, unchanged there
+            Some(original) if same(original, todo) => {}
+            // Deleted here, changed there
+            Some(_) => conflicts.push(Conflict { ours: None, theirs: Some(todo.clone()) }),
+            // New there
+            None => merged.push(todo.clone()),
+        }
+    }
+    // Subtasks of a renamed todo follow it
+    for todo in merged.iter_mut().filter(|t| !our_ids.contains(&t.id) || renamed.values().any(|id| *id == t.id)) {
+        if let Some(parent) = todo.parent_id.and_then(|p| renamed.get(&p)) {
+            todo.parent_id = Some(*parent);
+        }
+    }
+    (merged, conflicts)
+}
+
+/// Ids of the todos added, changed or deleted in `theirs` since `base`
+pub fn changed_ids(base: &[Todo], theirs: &[Todo]) -> HashSet<usize> {
+    let base_map: HashMap<usize, &Todo> = base.iter().map(|t| (t.id, t)).collect();
+    let their_map: HashMap<usize, &Todo> = theirs.iter().map(|t| (t.id, t)).collect();
+    let changed = theirs.iter().filter(|t| !base_map.get(&t.id).is_some_and(|b| same(b, t)));
+    let deleted = base.iter().filter(|b| !their_map.contains_key(&b.id));
+    changed.chain(deleted).map(|t| t.id).collect()
+}
+
+/// Equal apart from `position`, which shifts whenever anything is added or
+/// removed before the todo
+pub fn same(a: &Todo, b: &Todo) -> bool {
+    let value = |todo: &Todo| {
+        let mut todo = todo.clone();
+        todo.position = 0;
+        serde_json::to_value(todo).ok()
+    };
+    value(a) == value(b)
+}
@@ -3,4 +3,15 @@ pub mod todo;
 pub mod event;
 pub mod  browser;
 pub mod player;
+pub mod filter;
+pub mod history;
+pub mod command;
+pub mod line_editor;
+pub mod sort;
+pub mod archive;
+pub mod stats;
+pub mod pomodoro;
+pub mod merge;
+pub mod timelog;
+pub mod workflow;
 
@@ -146,27 +146,8 @@ impl MusicPlayer {
             }
             KeyCode::Char('j') => self.image_offset.1 += 1,
             KeyCode::Char(' ') => {
-                if let Some(sink) = &self.sink {
-                    if self.is_paused {
-                        // Resuming playback
-                        sink.play();
-                        if let Some(pause_time) = self.pause_start {
-                            // Add the pause duration to elapsed_before_pause
-                            self.elapsed_before_pause += pause_time.elapsed();
-                        }
-                        self.pause_start = None;
-                        self.start_time = Some(Instant::now());
-                    } else {
-                        // Pausing playback
-                        sink.pause();
-                        self.pause_start = Some(Instant::now());
-                        // When pausing, update elapsed_before_pause with the time since last start/resume
-                        if let Some(start) = self.start_time {
-                            self.elapsed_before_pause += start.elapsed();
-                        }
-                    }
-                    self.is_paused = !self.is_paused;
-                }
+                let paused = self.is_paused;
+                self.set_paused(!paused);
             }
             KeyCode::Char('r') => {
                 if let Err(e) = self.reload_audio() {
@@ -192,6 +173,39 @@ impl MusicPlayer {
             _ => {}
         }
     }
+    pub fn set_paused(&mut self, paused: bool) {
+        if paused == self.is_paused {
+            return;
+        }
+        if let Some(sink) = &self.sink {
+            if self.is_paused {
+                // Resuming playback
+                sink.play();
+                if let Some(pause_time) = self.pause_start {
+                    // Add the pause duration to elapsed_before_pause
+                    self.elapsed_before_pause += pause_time.elapsed();
+                }
+                self.pause_start = None;
+                self.start_time = Some(Instant::now());
+            } else {
+                // Pausing playback
+                sink.pause();
+                self.pause_start = Some(Instant::now());
+                // When pausing, update elapsed_before_pause with the time since last start/resume
+                if let Some(start) = self.start_time {
+                    self.elapsed_before_pause += start.elapsed();
+                }
+            }
+            self.is_paused = !self.is_paused;
+        }
+    }
+
+    pub fn set_volume(&mut self, volume: f32) {
+        if let Some(sink) = &self.sink {
+            sink.set_volume(volume);
+        }
+    }
+
     pub fn update(&mut self) {
         if !self.is_paused {
             self.vinyl_angle += self.vinyl_speed;
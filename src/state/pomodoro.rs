@@ -0,0 +1,140 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::utils::config::PomodoroConfig;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Phase {
+    Focus,
+    Break,
+}
+
+impl Phase {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Phase::Focus => "Focus",
+            Phase::Break => "Break",
+        }
+    }
+}
+
+/// A running focus/break timer for one todo
+pub struct Pomodoro {
+    pub todo_id: usize,
+    pub phase: Phase,
+    /// Focus sessions finished since the timer was started
+    pub completed: u32,
+    /// Whether the music player was started for this timer, and so should
+    /// be stopped with it
+    pub owns_music: bool,
+    /// Focus music queue and the index of the current track
+    pub tracks: Vec<PathBuf>,
+    pub track: usize,
+    focus: Duration,
+    rest: Duration,
+    phase_started: Instant,
+    paused_at: Option<Instant>,
+}
+
+impl Pomodoro {
+    pub fn new(todo_id: usize, config: &PomodoroConfig) -> Self {
+        Self {
+            todo_id,
+            phase: Phase::Focus,
+            completed: 0,
+            owns_music: false,
+            tracks: config.music.as_deref().map(music_tracks).unwrap_or_default(),
+            track: 0,
+            focus: Duration::from_secs(config.focus_minutes.max(1) * 60),
+            rest: Duration::from_secs(config.break_minutes.max(1) * 60),
+            phase_started: Instant::now(),
+            paused_at: None,
+        }
+    }
+
+    pub fn phase_length(&self) -> Duration {
+        match self.phase {
+            Phase::Focus => self.focus,
+            Phase::Break => self.rest,
+        }
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        let now = self.paused_at.unwrap_or_else(Instant::now);
+        now.duration_since(self.phase_started).min(self.phase_length())
+    }
+
+    pub fn remaining(&self) -> Duration {
+        self.phase_length() - self.elapsed()
+    }
+
+    /// Share of the current phase that has passed, from 0.0 to 1.0
+    pub fn progress(&self) -> f64 {
+        self.elapsed().as_secs_f64() / self.phase_length().as_secs_f64()
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused_at.is_some()
+    }
+
+    pub fn toggle_pause(&mut self) {
+        match self.paused_at.take() {
+            // Shift the start so the paused time does not count
+            Some(paused_at) => self.phase_started += paused_at.elapsed(),
+            None => self.paused_at = Some(Instant::now()),
+        }
+    }
+
+    /// Moves to the next phase once the current one is over and returns the
+    /// phase that just ended
+    pub fn tick(&mut self) -> Option<Phase> {
+        if self.is_paused() || self.elapsed() < self.phase_length() {
+            return None;
+        }
+        let ended = self.phase;
+        self.phase = match ended {
+            Phase::Focus => {
+                self.completed += 1;
+                Phase::Break
+            }
+            Phase::Break => Phase::Focus,
+        };
+        self.phase_started = Instant::now();
+        Some(ended)
+    }
+}
+
+/// Audio files to play for a music setting: the file itself, the audio files
+/// in a folder sorted by name, or the entries of an `.m3u` playlist
+pub fn music_tracks(path: &Path) -> Vec<PathBuf> {
+    const AUDIO: [&str; 5] = ["mp3", "flac", "ogg", "wav", "m4a"];
+    let has_extension = |path: &Path, extensions: &[&str]| {
+        path.extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| extensions.contains(&e.to_lowercase().as_str()))
+    };
+    if path.is_dir() {
+        let mut tracks: Vec<PathBuf> = fs::read_dir(path)
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|p| has_extension(p, &AUDIO))
+            .collect();
+        tracks.sort();
+        return tracks;
+    }
+    if has_extension(path, &["m3u", "m3u8"]) {
+        let base = path.parent().unwrap_or(Path::new("."));
+        return fs::read_to_string(path)
+            .unwrap_or_default()
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            // Relative entries are relative to the playlist
+            .map(|line| base.join(line))
+            .collect();
+    }
+    vec![path.to_path_buf()]
+}
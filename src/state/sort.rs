@@ -0,0 +1,59 @@
+use std::cmp::Ordering;
+
+use serde::{Deserialize, Serialize};
+
+use crate::state::todo::Todo;
+
+/// How siblings are ordered in the todo list. Ties keep the manual order.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SortMode {
+    #[default]
+    Manual,
+    Priority,
+    Due,
+    Created,
+    Alphabetical,
+    CompletedLast,
+}
+
+impl SortMode {
+    pub fn next(self) -> SortMode {
+        match self {
+            SortMode::Manual => SortMode::Priority,
+            SortMode::Priority => SortMode::Due,
+            SortMode::Due => SortMode::Created,
+            SortMode::Created => SortMode::Alphabetical,
+            SortMode::Alphabetical => SortMode::CompletedLast,
+            SortMode::CompletedLast => SortMode::Manual,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SortMode::Manual => "manual",
+            SortMode::Priority => "priority",
+            SortMode::Due => "due date",
+            SortMode::Created => "created",
+            SortMode::Alphabetical => "a-z",
+            SortMode::CompletedLast => "completed last",
+        }
+    }
+
+    pub fn compare(self, a: &Todo, b: &Todo) -> Ordering {
+        match self {
+            SortMode::Manual => Ordering::Equal,
+            // Highest first, unprioritized last
+            SortMode::Priority => b.priority.cmp(&a.priority),
+            // Soonest first, undated last
+            SortMode::Due => match (a.due, b.due) {
+                (Some(a), Some(b)) => a.cmp(&b),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            },
+            SortMode::Created => a.created_at.cmp(&b.created_at),
+            SortMode::Alphabetical => a.title.to_lowercase().cmp(&b.title.to_lowercase()),
+            SortMode::CompletedLast => a.is_done().cmp(&b.is_done()),
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use chrono::{Duration, Local, NaiveDate};
+use std::collections::BTreeSet;
+
+use crate::state::todo::Todo;
+
+/// How many days the per-day charts cover
+pub const STATS_DAYS: i64 = 14;
+
+/// Numbers for the stats screen, computed from the working list and the
+/// archive
+pub struct Stats {
+    /// (day, count) for the last `STATS_DAYS` days, oldest first
+    pub created_per_day: Vec<(NaiveDate, u64)>,
+    pub completed_per_day: Vec<(NaiveDate, u64)>,
+    /// Days in a row, up to today, with at least one completion. A day with
+    /// nothing done yet does not break the streak until it is over.
+    pub current_streak: usize,
+    pub longest_streak: usize,
+    pub average_completion: Option<Duration>,
+    pub open: usize,
+    pub completed: usize,
+    pub overdue: usize,
+    pub due_today: usize,
+}
+
+impl Stats {
+    pub fn compute<'a>(todos: impl IntoIterator<Item = &'a Todo>, today: NaiveDate) -> Stats {
+        let mut created = Vec::new();
+        let mut completions = Vec::new();
+        let mut durations = Vec::new();
+        let (mut open, mut completed, mut overdue, mut due_today) = (0, 0, 0, 0);
+        for todo in todos {
+            created.push(todo.created_at.date_naive());
+            // Each completed occurrence of a recurring todo stays behind as a
+            // done todo, so `completion_history` would count it twice
+            if todo.is_done() {
+                completed += 1;
+                if let Some(at) = todo.completed_at {
+                    completions.push(at.date_naive());
+                    durations.push(at - todo.created_at);
+                }
+            } else {
+                open += 1;
+            }
+            if todo.is_overdue(today) {
+                overdue += 1;
+            }
+            if todo.is_due_today(today) {
+                due_today += 1;
+            }
+        }
+
+        let days = completions.iter().copied().collect::<BTreeSet<_>>();
+        let (current_streak, longest_streak) = streaks(&days, today);
+        let average_completion = match durations.len() {
+            0 => None,
+            count => Some(durations.iter().fold(Duration::zero(), |sum, d| sum + *d) / count as i32),
+        };
+
+        Stats {
+            created_per_day: per_day(&created, today),
+            completed_per_day: per_day(&completions, today),
+            current_streak,
+            longest_streak,
+            average_completion,
+            open,
+            completed,
+            overdue,
+            due_today,
+        }
+    }
+
+    pub fn today() -> NaiveDate {
+        Local::now().date_naive()
+    }
+}
+
+fn per_day(dates: &[NaiveDate], today: NaiveDate) -> Vec<(NaiveDate, u64)> {
+    (0..STATS_DAYS)
+        .rev()
+        .map(|offset| {
+            let day = today - Duration::days(offset);
+            (day, dates.iter().filter(|d| **d == day).count() as u64)
+        })
+        .collect()
+}
+
+fn streaks(days: &BTreeSet<NaiveDate>, today: NaiveDate) -> (usize, usize) {
+    let mut longest = 0;
+    let mut run = 0;
+    let mut previous: Option<NaiveDate> = None;
+    for day in days {
+        run = match previous {
+            Some(previous) if *day - previous == Duration::days(1) => run + 1,
+            _ => 1,
+        };
+        longest = longest.max(run);
+        previous = Some(*day);
+    }
+
+    let mut current = 0;
+    let mut day = if days.contains(&today) { today } else { today - Duration::days(1) };
+    while days.contains(&day) {
+        current += 1;
+        day -= Duration::days(1);
+    }
+    (current, longest)
+}
@@ -0,0 +1,208 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::state::todo::Todo;
+use crate::utils::storage::TodoStorage;
+
+/// Time spent on a todo; `end` is None while the timer runs
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TimeEntry {
+    pub start: DateTime<Local>,
+    #[serde(default)]
+    pub end: Option<DateTime<Local>>,
+}
+
+impl TimeEntry {
+    pub fn start(at: DateTime<Local>) -> Self {
+        Self { start: at, end: None }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.end.is_none()
+    }
+
+    pub fn duration(&self, now: DateTime<Local>) -> Duration {
+        (self.end.unwrap_or(now) - self.start).max(Duration::zero())
+    }
+
+    // The part of the entry that falls on `day`
+    fn on_day(&self, day: NaiveDate, now: DateTime<Local>) -> Option<(DateTime<Local>, DateTime<Local>)> {
+        let midnight = |day: NaiveDate| Local.from_local_datetime(&day.and_hms_opt(0, 0, 0)?).earliest();
+        let start = self.start.max(midnight(day)?);
+        let end = self.end.unwrap_or(now).min(midnight(day.succ_opt()?)?);
+        (start < end).then_some((start, end))
+    }
+}
+
+/// Days a report covers; the presets are cycled with Tab on the report screen
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReportRange {
+    Today,
+    ThisWeek,
+    ThisMonth,
+    Last30Days,
+    /// From and to, both included
+    Custom(NaiveDate, NaiveDate),
+}
+
+impl ReportRange {
+    pub fn next(&self) -> ReportRange {
+        match self {
+            ReportRange::Today => ReportRange::ThisWeek,
+            ReportRange::ThisWeek => ReportRange::ThisMonth,
+            ReportRange::ThisMonth => ReportRange::Last30Days,
+            ReportRange::Last30Days | ReportRange::Custom(..) => ReportRange::Today,
+        }
+    }
+
+    /// First and last day, both included
+    pub fn days(&self, today: NaiveDate) -> (NaiveDate, NaiveDate) {
+        match *self {
+            ReportRange::Today => (today, today),
+            ReportRange::ThisWeek => (today - Duration::days(today.weekday().num_days_from_monday() as i64), today),
+            ReportRange::ThisMonth => (today.with_day(1).unwrap_or(today), today),
+            ReportRange::Last30Days => (today - Duration::days(29), today),
+            ReportRange::Custom(from, to) => (from, to),
+        }
+    }
+
+    pub fn label(&self, today: NaiveDate) -> String {
+        let (from, to) = self.days(today);
+        let name = match self {
+            ReportRange::Today => "Today",
+            ReportRange::ThisWeek => "This week",
+            ReportRange::ThisMonth => "This month",
+            ReportRange::Last30Days => "Last 30 days",
+            ReportRange::Custom(..) => "Custom",
+        };
+        format!("{} ({} to {})", name, from.format("%Y-%m-%d"), to.format("%Y-%m-%d"))
+    }
+}
+
+/// One entry, or the part of it inside the report range, for one day
+pub struct ReportRow {
+    pub day: NaiveDate,
+    pub todo_id: usize,
+    pub title: String,
+    pub project: Option<String>,
+    pub tags: Vec<String>,
+    pub start: DateTime<Local>,
+    pub end: DateTime<Local>,
+}
+
+impl ReportRow {
+    pub fn duration(&self) -> Duration {
+        self.end - self.start
+    }
+}
+
+/// Tracked time within a date range, summed per todo, tag and day
+pub struct TimeReport {
+    pub rows: Vec<ReportRow>,
+    /// (title, time), most time first
+    pub per_todo: Vec<(String, Duration)>,
+    /// Todos without tags are summed under "(untagged)"; a todo with several
+    /// tags counts towards each
+    pub per_tag: Vec<(String, Duration)>,
+    /// Every day of the range, oldest first
+    pub per_day: Vec<(NaiveDate, Duration)>,
+    pub total: Duration,
+}
+
+impl TimeReport {
+    pub fn compute<'a>(todos: impl IntoIterator<Item = &'a Todo>, range: ReportRange, now: DateTime<Local>) -> TimeReport {
+        let (from, to) = range.days(now.date_naive());
+        let mut rows = Vec::new();
+        for todo in todos {
+            for entry in &todo.time_entries {
+                let mut day = from.max(entry.start.date_naive());
+                while day <= to && day <= entry.end.unwrap_or(now).date_naive() {
+                    if let Some((start, end)) = entry.on_day(day, now) {
+                        rows.push(ReportRow {
+                            day,
+                            todo_id: todo.id,
+                            title: todo.title.clone(),
+                            project: todo.project.clone(),
+                            tags: todo.tags.clone(),
+                            start,
+                            end,
+                        });
+                    }
+                    day = match day.succ_opt() {
+                        Some(next) => next,
+                        None => break,
+                    };
+                }
+            }
+        }
+        rows.sort_by_key(|row| row.start);
+
+        let mut per_todo: BTreeMap<(usize, String), Duration> = BTreeMap::new();
+        let mut per_tag: BTreeMap<String, Duration> = BTreeMap::new();
+        let mut per_day: BTreeMap<NaiveDate, Duration> = from.iter_days().take_while(|d| *d <= to).map(|d| (d, Duration::zero())).collect();
+        for row in &rows {
+            *per_todo.entry((row.todo_id, row.title.clone())).or_insert_with(Duration::zero) += row.duration();
+            if row.tags.is_empty() {
+                *per_tag.entry("(untagged)".to_string()).or_insert_with(Duration::zero) += row.duration();
+            }
+            for tag in &row.tags {
+                *per_tag.entry(format!("@{}", tag)).or_insert_with(Duration::zero) += row.duration();
+            }
+            *per_day.entry(row.day).or_insert_with(Duration::zero) += row.duration();
+        }
+        let by_time = |mut sums: Vec<(String, Duration)>| {
+            sums.sort_by(|a, b| b.1.cmp(&a.1));
+            sums
+        };
+
+        TimeReport {
+            total: rows.iter().fold(Duration::zero(), |sum, row| sum + row.duration()),
+            per_todo: by_time(per_todo.into_iter().map(|((_, title), time)| (title, time)).collect()),
+            per_tag: by_time(per_tag.into_iter().collect()),
+            per_day: per_day.into_iter().collect(),
+            rows,
+        }
+    }
+
+    /// Writes one line per entry and day, with the time in decimal hours
+    pub fn write_csv(&self, path: &Path) -> Result<()> {
+        let mut csv = String::from("date,todo_id,todo,project,tags,start,end,hours\n");
+        for row in &self.rows {
+            let fields = [
+                row.day.format("%Y-%m-%d").to_string(),
+                row.todo_id.to_string(),
+                row.title.clone(),
+                row.project.clone().unwrap_or_default(),
+                row.tags.join(" "),
+                row.start.format("%Y-%m-%d %H:%M:%S").to_string(),
+                row.end.format("%Y-%m-%d %H:%M:%S").to_string(),
+                format!("{:.2}", row.duration().num_seconds() as f64 / 3600.0),
+            ];
+            let fields: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+            csv.push_str(&fields.join(","));
+            csv.push('\n');
+        }
+        TodoStorage::write_atomic(path, csv.as_bytes()).with_context(|| format!("failed to write {}", path.display()))
+    }
+}
+
+// Quotes a field if it holds a comma, quote or line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// `1h 05m`, or `12m 30s` under an hour
+pub fn format_duration(duration: Duration) -> String {
+    let seconds = duration.num_seconds().max(0);
+    match seconds / 3600 {
+        0 => format!("{}m {:02}s", seconds / 60, seconds % 60),
+        hours => format!("{}h {:02}m", hours, seconds % 3600 / 60),
+    }
+}
@@ -1,13 +1,217 @@
-use chrono::{DateTime, Local};
-use serde::Deserialize;
-use  serde::Serialize;
+use chrono::{DateTime, Datelike, Duration, Local, Months, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Weekday};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
+use crate::state::sort::SortMode;
+use crate::state::timelog::TimeEntry;
+use crate::state::workflow::{DEFAULT_STATE, DONE_STATE};
 
-#[derive(Serialize,Deserialize)]
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Priority {
+    Low,
+    Medium,
+    High,
+}
+
+impl Priority {
+    pub fn parse(value: &str) -> Option<Priority> {
+        match value.to_lowercase().as_str() {
+            "l" | "low" => Some(Priority::Low),
+            "m" | "med" | "medium" => Some(Priority::Medium),
+            "h" | "high" => Some(Priority::High),
+            _ => None,
+        }
+    }
+
+    /// Steps through None -> Low -> Medium -> High -> None
+    pub fn cycle(current: Option<Priority>) -> Option<Priority> {
+        match current {
+            None => Some(Priority::Low),
+            Some(Priority::Low) => Some(Priority::Medium),
+            Some(Priority::Medium) => Some(Priority::High),
+            Some(Priority::High) => None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Priority::Low => "low",
+            Priority::Medium => "med",
+            Priority::High => "high",
+        }
+    }
+}
+
+/// How a todo repeats once it is completed
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum Recurrence {
+    Daily,
+    /// On the given weekdays; an empty list means the due date's weekday
+    Weekly(Vec<Weekday>),
+    /// On day N of the month, clamped to the month's last day
+    Monthly(u32),
+    /// N days after the todo was actually completed
+    AfterCompletion(u32),
+}
+
+impl Recurrence {
+    /// Parses `daily`, `weekly`, `weekly:mon,thu`, `monthly:15` or `+3d`
+    pub fn parse(value: &str) -> Option<Recurrence> {
+        let value = value.to_lowercase();
+        if value == "daily" {
+            return Some(Recurrence::Daily);
+        }
+        if value == "weekly" {
+            return Some(Recurrence::Weekly(Vec::new()));
+        }
+        if let Some(days) = value.strip_prefix("weekly:") {
+            let weekdays: Option<Vec<Weekday>> = days.split(',').map(|d| d.parse().ok()).collect();
+            return weekdays.filter(|w| !w.is_empty()).map(Recurrence::Weekly);
+        }
+        if let Some(day) = value.strip_prefix("monthly:") {
+            return day.parse().ok().filter(|d| (1..=31).contains(d)).map(Recurrence::Monthly);
+        }
+        if let Some(days) = value.strip_prefix('+').and_then(|v| v.strip_suffix('d')) {
+            return days.parse().ok().filter(|&d| d > 0).map(Recurrence::AfterCompletion);
+        }
+        None
+    }
+
+    /// The due date of the occurrence after one due on `due` and completed
+    /// on `completed`. Scheduled rules skip occurrences already in the past.
+    pub fn next_due(&self, due: Option<NaiveDate>, completed: NaiveDate) -> NaiveDate {
+        let mut next = due.unwrap_or(completed);
+        loop {
+            next = match self {
+                Recurrence::Daily => next + Duration::days(1),
+                Recurrence::Weekly(days) if days.is_empty() => next + Duration::days(7),
+                Recurrence::Weekly(days) => {
+                    let mut day = next + Duration::days(1);
+                    while !days.contains(&day.weekday()) {
+                        day += Duration::days(1);
+                    }
+                    day
+                }
+                Recurrence::Monthly(day) => {
+                    let month = next.with_day(1).unwrap_or(next) + Months::new(1);
+                    let last_day = (month + Months::new(1) - Duration::days(1)).day();
+                    month.with_day((*day).min(last_day)).unwrap_or(month)
+                }
+                Recurrence::AfterCompletion(days) => return completed + Duration::days(*days as i64),
+            };
+            if next > completed {
+                return next;
+            }
+        }
+    }
+}
+
+impl fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Recurrence::Daily => write!(f, "daily"),
+            Recurrence::Weekly(days) if days.is_empty() => write!(f, "weekly"),
+            Recurrence::Weekly(days) => {
+                let days: Vec<String> = days.iter().map(|d| d.to_string().to_lowercase()).collect();
+                write!(f, "weekly:{}", days.join(","))
+            }
+            Recurrence::Monthly(day) => write!(f, "monthly:{}", day),
+            Recurrence::AfterCompletion(days) => write!(f, "+{}d", days),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(remote = "Self")]
 pub struct Todo {
     pub id: usize,
     pub title: String,
-    pub completed: bool,
+    /// Workflow state such as `In Progress`; `DONE_STATE` means completed
+    #[serde(default)]
+    pub state: String,
+    #[serde(default)]
+    pub priority: Option<Priority>,
+    #[serde(default)]
+    pub due: Option<NaiveDate>,
+    #[serde(default)]
+    pub scheduled: Option<NaiveDate>,
+    #[serde(default)]
+    pub completed_at: Option<DateTime<Local>>,
+    #[serde(default = "Local::now")]
+    pub created_at: DateTime<Local>,
+    #[serde(default)]
+    pub parent_id: Option<usize>,
+    #[serde(default)]
+    pub collapsed: bool,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub project: Option<String>,
+    /// Extra `key:value` pairs, e.g. from todo.txt extensions
+    #[serde(default)]
+    pub attributes: BTreeMap<String, String>,
+    #[serde(default)]
+    pub recurrence: Option<Recurrence>,
+    /// When earlier occurrences of a recurring todo were completed
+    #[serde(default)]
+    pub completion_history: Vec<DateTime<Local>>,
+    /// Free-form Markdown shown in the detail pane
+    #[serde(default)]
+    pub notes: String,
+    /// Name of the list the todo belongs to
+    #[serde(default = "default_list")]
+    pub list: String,
+    /// When to pop up a reminder
+    #[serde(default)]
+    pub remind_at: Option<DateTime<Local>>,
+    /// Set once the reminder at `remind_at` has been shown and dismissed
+    #[serde(default)]
+    pub reminder_dismissed: bool,
+    /// When each Pomodoro focus session on this todo was finished
+    #[serde(default)]
+    pub pomodoros: Vec<DateTime<Local>>,
+    /// Tracked time, oldest first; at most the last entry is still running
+    #[serde(default)]
+    pub time_entries: Vec<TimeEntry>,
+    /// Manual order, kept equal to the todo's index in the list
+    #[serde(default)]
+    pub position: usize,
+    /// Fields written by newer versions, kept so saving does not drop them
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+// `completed` lands in `extra`. It is still written so older versions can
+// read newer files, and those versions toggle it without knowing `state`, so
+// when the two disagree `completed` is the newer of them and wins.
+impl<'de> Deserialize<'de> for Todo {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut todo = Todo::deserialize(deserializer)?;
+        let completed = todo.extra.remove("completed").and_then(|v| v.as_bool());
+        match completed {
+            Some(done) if done != todo.is_done() || todo.state.is_empty() => todo.set_done(done),
+            None if todo.state.is_empty() => todo.set_done(false),
+            _ => {}
+        }
+        Ok(todo)
+    }
+}
+
+impl Serialize for Todo {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut todo = self.clone();
+        todo.extra.insert("completed".to_string(), serde_json::Value::Bool(self.is_done()));
+        Todo::serialize(&todo, serializer)
+    }
+}
+
+/// The list todos saved before lists existed end up in
+pub const DEFAULT_LIST: &str = "Inbox";
+
+fn default_list() -> String {
+    DEFAULT_LIST.to_string()
 }
 
 impl Todo {
@@ -15,18 +219,325 @@ impl Todo {
         Self {
             id,
             title,
-            completed: false,
+            state: DEFAULT_STATE.to_string(),
+            priority: None,
+            due: None,
+            scheduled: None,
+            completed_at: None,
+            created_at: Local::now(),
+            parent_id: None,
+            collapsed: false,
+            tags: Vec::new(),
+            project: None,
+            attributes: BTreeMap::new(),
+            recurrence: None,
+            completion_history: Vec::new(),
+            notes: String::new(),
+            list: default_list(),
+            remind_at: None,
+            reminder_dismissed: false,
+            pomodoros: Vec::new(),
+            time_entries: Vec::new(),
+            position: 0,
+            extra: BTreeMap::new(),
+        }
+    }
+
+    /// Builds a todo from the input box, picking out inline tokens such as
+    /// `due:2025-01-31`, `start:tomorrow`, `rec:weekly:mon`,
+    /// `remind:2025-01-31T09:00`, `!high`, `+project` and `@tag` from the title.
+    pub fn from_input(id: usize, input: &str) -> Self {
+        let mut todo = Todo::new(id, String::new());
+        let mut words = Vec::new();
+        for word in input.split_whitespace() {
+            if let Some(value) = word.strip_prefix("due:") {
+                if let Some(date) = parse_date(value) {
+                    todo.due = Some(date);
+                    continue;
+                }
+            } else if let Some(value) = word.strip_prefix("start:") {
+                if let Some(date) = parse_date(value) {
+                    todo.scheduled = Some(date);
+                    continue;
+                }
+            } else if let Some(value) = word.strip_prefix("remind:") {
+                if let Some(at) = parse_date_time(value) {
+                    todo.remind_at = Some(at);
+                    continue;
+                }
+            } else if let Some(value) = word.strip_prefix("rec:") {
+                if let Some(recurrence) = Recurrence::parse(value) {
+                    todo.recurrence = Some(recurrence);
+                    continue;
+                }
+            } else if let Some(value) = word.strip_prefix('!') {
+                if let Some(priority) = Priority::parse(value) {
+                    todo.priority = Some(priority);
+                    continue;
+                }
+            } else if let Some(project) = word.strip_prefix('+').filter(|p| !p.is_empty()) {
+                todo.project = Some(project.to_string());
+                continue;
+            } else if let Some(tag) = word.strip_prefix('@').filter(|t| !t.is_empty()) {
+                if !todo.tags.iter().any(|t| t == tag) {
+                    todo.tags.push(tag.to_string());
+                }
+                continue;
+            }
+            words.push(word);
+        }
+        todo.title = words.join(" ");
+        todo
+    }
+
+    /// The inverse of `from_input`: the title followed by its inline tokens
+    pub fn to_input(&self) -> String {
+        let mut parts = vec![self.title.clone()];
+        if let Some(project) = &self.project {
+            parts.push(format!("+{}", project));
+        }
+        for tag in &self.tags {
+            parts.push(format!("@{}", tag));
+        }
+        if let Some(priority) = self.priority {
+            parts.push(format!("!{}", priority.label()));
+        }
+        if let Some(scheduled) = self.scheduled {
+            parts.push(format!("start:{}", scheduled.format("%Y-%m-%d")));
+        }
+        if let Some(due) = self.due {
+            parts.push(format!("due:{}", due.format("%Y-%m-%d")));
+        }
+        if let Some(recurrence) = &self.recurrence {
+            parts.push(format!("rec:{}", recurrence));
+        }
+        if let Some(remind_at) = self.remind_at {
+            parts.push(format!("remind:{}", remind_at.format("%Y-%m-%dT%H:%M")));
+        }
+        parts.join(" ")
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.state == DONE_STATE
+    }
+
+    /// Marks the todo done or open without touching its timestamps, as
+    /// importers do
+    pub fn set_done(&mut self, done: bool) {
+        self.state = if done { DONE_STATE } else { DEFAULT_STATE }.to_string();
+    }
+
+    /// Moves the todo to `state`, stamping the completion time when it
+    /// becomes done and clearing it when it is reopened
+    pub fn set_state(&mut self, state: &str) {
+        let was_done = self.is_done();
+        self.state = state.to_string();
+        let now = Local::now();
+        match (was_done, self.is_done()) {
+            (false, true) => {
+                self.completed_at = Some(now);
+                self.stop_timer(now);
+            }
+            (true, false) => self.completed_at = None,
+            _ => {}
         }
     }
+
     pub fn toggle_complete(&mut self) {
-        self.completed = !self.completed;
+        self.set_state(if self.is_done() { DEFAULT_STATE } else { DONE_STATE });
+    }
+
+    pub fn running_timer(&self) -> Option<&TimeEntry> {
+        self.time_entries.last().filter(|entry| entry.is_running())
+    }
+
+    pub fn stop_timer(&mut self, at: DateTime<Local>) {
+        if let Some(entry) = self.time_entries.last_mut().filter(|entry| entry.is_running()) {
+            entry.end = Some(at);
+        }
+    }
+
+    pub fn tracked_time(&self, now: DateTime<Local>) -> Duration {
+        self.time_entries.iter().fold(Duration::zero(), |sum, entry| sum + entry.duration(now))
+    }
+
+    /// The next occurrence of a recurring todo completed at `completed_at`,
+    /// or None if it does not repeat. It starts in the `reopened` state, and
+    /// the start date keeps its distance from the due date.
+    pub fn next_occurrence(&self, id: usize, completed_at: DateTime<Local>, reopened: &str) -> Option<Todo> {
+        let recurrence = self.recurrence.as_ref()?;
+        let next_due = recurrence.next_due(self.due, completed_at.date_naive());
+        let mut next = self.clone();
+        next.id = id;
+        next.state = reopened.to_string();
+        next.completed_at = None;
+        next.created_at = Local::now();
+        next.scheduled = match (self.scheduled, self.due) {
+            (Some(scheduled), Some(due)) => Some(next_due - (due - scheduled)),
+            _ => None,
+        };
+        // The reminder keeps its distance from the due date
+        next.remind_at = match (self.remind_at, self.due) {
+            (Some(remind_at), Some(due)) => Some(remind_at + (next_due - due)),
+            _ => None,
+        };
+        next.reminder_dismissed = false;
+        next.due = Some(next_due);
+        next.completion_history.push(completed_at);
+        // Time belongs to the occurrence it was spent on
+        next.time_entries.clear();
+        Some(next)
+    }
+
+    pub fn is_overdue(&self, today: NaiveDate) -> bool {
+        !self.is_done() && self.due.is_some_and(|due| due < today)
+    }
+
+    pub fn is_due_today(&self, today: NaiveDate) -> bool {
+        !self.is_done() && self.due == Some(today)
     }
 }
 
+/// A todo as it appears in the rendered tree
+#[derive(Clone, Copy, Debug)]
+pub struct TodoRow {
+    pub index: usize,
+    pub depth: usize,
+}
+
+/// Flattens the parent links into display order, skipping the children of
+/// collapsed todos. Siblings are ordered by `sort`. Todos whose parent no
+/// longer exists are shown as roots.
+pub fn tree_rows(todos: &[Todo], sort: SortMode) -> Vec<TodoRow> {
+    build_rows(todos, false, sort)
+}
+
+/// Like `tree_rows`, but in manual order and including the children of
+/// collapsed todos
+pub fn all_rows(todos: &[Todo]) -> Vec<TodoRow> {
+    build_rows(todos, true, SortMode::Manual)
+}
+
+fn build_rows(todos: &[Todo], include_collapsed: bool, sort: SortMode) -> Vec<TodoRow> {
+    fn visit(todos: &[Todo], order: &[usize], parent: usize, depth: usize, include_collapsed: bool, rows: &mut Vec<TodoRow>) {
+        for &index in order {
+            let todo = &todos[index];
+            if todo.parent_id == Some(parent) {
+                rows.push(TodoRow { index, depth });
+                if include_collapsed || !todo.collapsed {
+                    visit(todos, order, todo.id, depth + 1, include_collapsed, rows);
+                }
+            }
+        }
+    }
+
+    // Stable, so ties stay in manual order
+    let mut order: Vec<usize> = (0..todos.len()).collect();
+    order.sort_by(|&a, &b| sort.compare(&todos[a], &todos[b]));
+
+    let mut rows = Vec::new();
+    for &index in &order {
+        let todo = &todos[index];
+        let is_root = match todo.parent_id {
+            None => true,
+            Some(parent) => !todos.iter().any(|t| t.id == parent),
+        };
+        if is_root {
+            rows.push(TodoRow { index, depth: 0 });
+            if include_collapsed || !todo.collapsed {
+                visit(todos, &order, todo.id, 1, include_collapsed, &mut rows);
+            }
+        }
+    }
+    rows
+}
+
+pub fn has_children(todos: &[Todo], id: usize) -> bool {
+    todos.iter().any(|t| t.parent_id == Some(id))
+}
+
+/// Ids of every todo below `id`, children before grandchildren
+pub fn descendant_ids(todos: &[Todo], id: usize) -> Vec<usize> {
+    let mut ids = Vec::new();
+    let mut frontier = vec![id];
+    while let Some(parent) = frontier.pop() {
+        for todo in todos.iter().filter(|t| t.parent_id == Some(parent)) {
+            // Guard against cycles in hand-edited files
+            if todo.id != id && !ids.contains(&todo.id) {
+                ids.push(todo.id);
+                frontier.push(todo.id);
+            }
+        }
+    }
+    ids
+}
+
+/// (completed, total) over the descendants of every todo that has any, in
+/// one pass over the ancestors of each todo. Leaves have no entry.
+pub fn completion_fractions(todos: &[Todo]) -> HashMap<usize, (usize, usize)> {
+    let parents: HashMap<usize, usize> = todos.iter().filter_map(|t| Some((t.id, t.parent_id?))).collect();
+    let mut fractions = HashMap::new();
+    for todo in todos {
+        // Guard against cycles in hand-edited files
+        let mut seen = vec![todo.id];
+        let mut parent = todo.parent_id;
+        while let Some(id) = parent.filter(|id| !seen.contains(id)) {
+            let (done, total) = fractions.entry(id).or_insert((0, 0));
+            *total += 1;
+            if todo.is_done() {
+                *done += 1;
+            }
+            seen.push(id);
+            parent = parents.get(&id).copied();
+        }
+    }
+    fractions
+}
+
+/// Accepts `YYYY-MM-DD`, `today`, `tomorrow` or a relative `+Nd` offset.
+pub fn parse_date(value: &str) -> Option<NaiveDate> {
+    let today = Local::now().date_naive();
+    match value {
+        "today" => Some(today),
+        "tomorrow" => Some(today + Duration::days(1)),
+        _ => {
+            if let Some(days) = value.strip_prefix('+').and_then(|v| v.strip_suffix('d')) {
+                return days.parse::<i64>().ok().map(|n| today + Duration::days(n));
+            }
+            NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()
+        }
+    }
+}
+
+/// Parses a reminder time: `YYYY-MM-DDTHH:MM`, `HH:MM` (the next time the
+/// clock shows it), or an offset from now such as `+30m`, `+2h` or `+1d`
+pub fn parse_date_time(value: &str) -> Option<DateTime<Local>> {
+    let now = Local::now();
+    if let Some(offset) = value.strip_prefix('+') {
+        let (amount, unit) = offset.split_at(offset.char_indices().last()?.0);
+        let amount: i64 = amount.parse().ok()?;
+        return match unit {
+            "m" => Some(now + Duration::minutes(amount)),
+            "h" => Some(now + Duration::hours(amount)),
+            "d" => Some(now + Duration::days(amount)),
+            _ => None,
+        };
+    }
+    if let Ok(time) = NaiveTime::parse_from_str(value, "%H:%M") {
+        let today = Local.from_local_datetime(&now.date_naive().and_time(time)).earliest()?;
+        return Some(if today > now { today } else { today + Duration::days(1) });
+    }
+    let naive = NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M").ok()?;
+    Local.from_local_datetime(&naive).earliest()
+}
+
 pub trait TodoManager {
      fn add_todo(&mut self, title:String);
+     fn add_subtask(&mut self, parent_id:usize, title:String);
      fn remove_todo(&mut self, id:usize);
      fn toggle_todo(&mut self, id:usize);
+     fn move_todo_up(&mut self, id:usize);
+     fn move_todo_down(&mut self, id:usize);
      fn get_todos(&self) -> &Vec<Todo>;
     
-}
\ No newline at end of file
+}
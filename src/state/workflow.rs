@@ -0,0 +1,70 @@
+use crate::state::todo::Todo;
+use crate::utils::config::WorkflowConfig;
+
+/// The state completed todos are in; always the last column of the board
+pub const DONE_STATE: &str = "Done";
+/// State for open todos saved before workflow states existed
+pub const DEFAULT_STATE: &str = "Todo";
+
+/// The configured workflow states, in board order and ending with `Done`
+#[derive(Clone, Debug)]
+pub struct Workflow {
+    states: Vec<String>,
+    initial: usize,
+}
+
+impl Workflow {
+    pub fn new(config: &WorkflowConfig) -> Self {
+        let mut states: Vec<String> = Vec::new();
+        for state in &config.states {
+            let state = state.trim();
+            if !state.is_empty() && state != DONE_STATE && !states.iter().any(|s| s == state) {
+                states.push(state.to_string());
+            }
+        }
+        if states.is_empty() {
+            states.push(DEFAULT_STATE.to_string());
+        }
+        let initial = states.iter().position(|s| *s == config.initial).unwrap_or(0);
+        states.push(DONE_STATE.to_string());
+        Self { states, initial }
+    }
+
+    pub fn states(&self) -> &[String] {
+        &self.states
+    }
+
+    /// The state new and reopened todos go to
+    pub fn initial(&self) -> &str {
+        &self.states[self.initial]
+    }
+
+    /// The state work on a todo is under way in: the first one after the
+    /// initial state, or the initial state itself if it is the last open one
+    pub fn in_progress(&self) -> &str {
+        let open = self.states.len() - 1;
+        &self.states[(self.initial + 1).min(open - 1)]
+    }
+
+    /// Moves todos in a state the workflow does not have, such as imported
+    /// ones or those reopened by an older version, to the initial state
+    pub fn normalize<'a>(&self, todos: impl IntoIterator<Item = &'a mut Todo>) {
+        for todo in todos {
+            if !self.states.contains(&todo.state) {
+                todo.state = self.initial().to_string();
+            }
+        }
+    }
+
+    /// The board column a todo is in. States no longer in the config count
+    /// as the initial state.
+    pub fn column(&self, todo: &Todo) -> usize {
+        self.states.iter().position(|s| *s == todo.state).unwrap_or(self.initial)
+    }
+}
+
+impl Default for Workflow {
+    fn default() -> Self {
+        Self::new(&WorkflowConfig::default())
+    }
+}
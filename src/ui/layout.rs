@@ -5,6 +5,7 @@ pub fn create_layout(area: Rect) -> Vec<Rect> {
     .direction(Direction::Vertical)
     .constraints([
         Constraint::Length(3),
+        Constraint::Length(1),
         Constraint::Min(0),
 
     ])
@@ -0,0 +1,69 @@
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, List, ListItem, ListState},
+};
+use chrono::Local;
+use crate::state::todo::Todo;
+
+pub struct BoardRenderer;
+
+impl BoardRenderer {
+    /// One column per workflow state, side by side. `columns` holds the
+    /// state names and the cards in each; the selected card is highlighted.
+    pub fn render_board(frame: &mut Frame, area: Rect, columns: &[(&str, Vec<&Todo>)], column: usize, row: usize, list: &str) {
+        let outer = Block::default()
+            .borders(Borders::ALL)
+            .title(format!("Board - {}", list))
+            .title_bottom("←/→ column | ↑/↓ card | H/L move card | B/Esc close");
+        let inner = outer.inner(area);
+        frame.render_widget(outer, area);
+
+        let constraints = vec![Constraint::Ratio(1, columns.len().max(1) as u32); columns.len()];
+        let areas = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(constraints)
+            .split(inner);
+
+        let today = Local::now().date_naive();
+        for (index, (state, cards)) in columns.iter().enumerate() {
+            let items: Vec<ListItem> = cards
+                .iter()
+                .map(|todo| {
+                    let mut lines = vec![Line::from(todo.title.clone())];
+                    let mut details = Vec::new();
+                    if let Some(priority) = todo.priority {
+                        details.push(Span::raw(format!("!{} ", priority.label())));
+                    }
+                    if let Some(due) = todo.due {
+                        let style = match todo.is_overdue(today) {
+                            true => Style::default().fg(Color::Red),
+                            false => Style::default().fg(Color::DarkGray),
+                        };
+                        details.push(Span::styled(format!("due {} ", due.format("%m-%d")), style));
+                    }
+                    for tag in &todo.tags {
+                        details.push(Span::styled(format!("@{} ", tag), Style::default().fg(Color::Green)));
+                    }
+                    if !details.is_empty() {
+                        lines.push(Line::from(details));
+                    }
+                    ListItem::new(lines)
+                })
+                .collect();
+
+            let selected = index == column;
+            let border = match selected {
+                true => Style::default().fg(Color::Yellow),
+                false => Style::default(),
+            };
+            let list = List::new(items)
+                .block(Block::default().borders(Borders::ALL).border_style(border).title(format!("{} ({})", state, cards.len())))
+                .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+            let mut state = ListState::default();
+            if selected && !cards.is_empty() {
+                state.select(Some(row.min(cards.len() - 1)));
+            }
+            frame.render_stateful_widget(list, areas[index], &mut state);
+        }
+    }
+}
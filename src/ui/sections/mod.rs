@@ -1,3 +1,8 @@
 pub mod todo_list;
 pub mod music_browser;
-pub mod player;
\ No newline at end of file
+pub mod player;
+pub mod notes;
+pub mod stats;
+pub mod pomodoro;
+pub mod time_report;
+pub mod board;
\ No newline at end of file
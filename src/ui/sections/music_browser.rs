@@ -65,10 +65,8 @@ impl BrowserRenderer {
         List::new(things)
             .block(Block::default().borders(Borders::ALL).title("Music Browser"))
             .style(match input_mode {
-                InputMode::Normal => Style::default(),
-                InputMode::Editing => Style::default(),
                 InputMode::Browser => Style::default().fg(Color::Blue),
-                InputMode::Player => Style::default(),
+                _ => Style::default(),
             })
     }
     
@@ -0,0 +1,155 @@
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Paragraph, Wrap},
+};
+use crate::state::todo::Todo;
+
+pub struct NotesRenderer;
+
+impl NotesRenderer {
+    /// Detail pane for the selected todo, with its notes rendered as Markdown
+    pub fn render_notes<'a>(todo: &Todo) -> Paragraph<'a> {
+        let text = if todo.notes.trim().is_empty() {
+            Text::styled("No notes. Press n to write some.", Style::default().fg(Color::DarkGray))
+        } else {
+            markdown_text(&todo.notes)
+        };
+        Paragraph::new(text)
+        .wrap(Wrap { trim: false })
+        .block(Block::default().borders(Borders::ALL).title(format!("Notes: {}", todo.title)))
+    }
+}
+
+/// Renders the common subset of Markdown: headings, bullet, numbered and
+/// task lists, block quotes, fenced code, and inline code, emphasis and links.
+pub fn markdown_text(source: &str) -> Text<'static> {
+    let mut lines = Vec::new();
+    let mut in_code = false;
+    for line in source.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") {
+            in_code = !in_code;
+            continue;
+        }
+        if in_code {
+            lines.push(Line::styled(format!("  {}", line), Style::default().fg(Color::Yellow)));
+            continue;
+        }
+        let indent = " ".repeat(line.len() - trimmed.len());
+
+        let heading = trimmed.chars().take_while(|c| *c == '#').count();
+        if (1..=6).contains(&heading) && trimmed[heading..].starts_with(' ') {
+            let mut style = Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD);
+            if heading == 1 {
+                style = style.add_modifier(Modifier::UNDERLINED);
+            }
+            lines.push(Line::styled(trimmed[heading + 1..].to_string(), style));
+            continue;
+        }
+        if let Some(quote) = trimmed.strip_prefix('>') {
+            let mut spans = vec![Span::styled("│ ", Style::default().fg(Color::DarkGray))];
+            spans.extend(inline_spans(quote.trim_start(), Style::default().add_modifier(Modifier::ITALIC)));
+            lines.push(Line::from(spans));
+            continue;
+        }
+        if let Some(item) = ["- ", "* ", "+ "].iter().find_map(|bullet| trimmed.strip_prefix(bullet)) {
+            let (marker, item) = if let Some(rest) = item.strip_prefix("[ ] ") {
+                ("☐ ", rest)
+            } else if let Some(rest) = item.strip_prefix("[x] ").or_else(|| item.strip_prefix("[X] ")) {
+                ("☑ ", rest)
+            } else {
+                ("• ", item)
+            };
+            let mut spans = vec![Span::raw(indent), Span::styled(marker, Style::default().fg(Color::Green))];
+            spans.extend(inline_spans(item, Style::default()));
+            lines.push(Line::from(spans));
+            continue;
+        }
+        if let Some((number, item)) = numbered_item(trimmed) {
+            let mut spans = vec![Span::raw(indent), Span::styled(format!("{}. ", number), Style::default().fg(Color::Green))];
+            spans.extend(inline_spans(item, Style::default()));
+            lines.push(Line::from(spans));
+            continue;
+        }
+        let mut spans = vec![Span::raw(indent)];
+        spans.extend(inline_spans(trimmed, Style::default()));
+        lines.push(Line::from(spans));
+    }
+    Text::from(lines)
+}
+
+// `12. item` -> ("12", "item")
+fn numbered_item(line: &str) -> Option<(&str, &str)> {
+    let digits = line.chars().take_while(|c| c.is_ascii_digit()).count();
+    if digits == 0 {
+        return None;
+    }
+    let rest = line[digits..].strip_prefix(". ")?;
+    Some((&line[..digits], rest))
+}
+
+/// Splits a line into spans for `code`, **bold**, *italic* / _italic_ and
+/// [links](url). Unclosed markers are shown as typed.
+fn inline_spans(text: &str, base: Style) -> Vec<Span<'static>> {
+    let mut spans = Vec::new();
+    let mut plain = String::new();
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        let styled = match c {
+            '`' => delimited(rest, "`").map(|(inner, len)| {
+                (vec![Span::styled(inner.to_string(), base.fg(Color::Yellow))], len)
+            }),
+            '*' if rest.starts_with("**") => delimited(rest, "**").map(|(inner, len)| {
+                (inline_spans(inner, base.add_modifier(Modifier::BOLD)), len)
+            }),
+            // Underscores inside words, as in snake_case, stay literal
+            '_' if plain.ends_with(|p: char| p.is_alphanumeric()) => None,
+            '*' | '_' => delimited(rest, &c.to_string()).map(|(inner, len)| {
+                (inline_spans(inner, base.add_modifier(Modifier::ITALIC)), len)
+            }),
+            '[' => link(rest).map(|(label, url, len)| {
+                (vec![
+                    Span::styled(label.to_string(), base.fg(Color::Blue).add_modifier(Modifier::UNDERLINED)),
+                    Span::styled(format!(" <{}>", url), base.fg(Color::DarkGray)),
+                ], len)
+            }),
+            _ => None,
+        };
+        match styled {
+            Some((styled, len)) => {
+                if !plain.is_empty() {
+                    spans.push(Span::styled(std::mem::take(&mut plain), base));
+                }
+                spans.extend(styled);
+                rest = &rest[len..];
+            }
+            None => {
+                plain.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+    if !plain.is_empty() {
+        spans.push(Span::styled(plain, base));
+    }
+    spans
+}
+
+// Text between a marker and its closing twin, plus the length consumed
+fn delimited<'t>(text: &'t str, marker: &str) -> Option<(&'t str, usize)> {
+    let inner = text.strip_prefix(marker)?;
+    let end = inner.find(marker)?;
+    if end == 0 {
+        return None;
+    }
+    Some((&inner[..end], marker.len() * 2 + end))
+}
+
+// `[label](url)` -> (label, url, length consumed)
+fn link(text: &str) -> Option<(&str, &str, usize)> {
+    let close = text.find("](")?;
+    let label = &text[1..close];
+    let after = &text[close + 2..];
+    let end = after.find(')')?;
+    Some((label, &after[..end], close + 2 + end + 1))
+}
@@ -0,0 +1,34 @@
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Gauge},
+};
+use crate::state::pomodoro::{Phase, Pomodoro};
+
+pub struct PomodoroRenderer;
+
+impl PomodoroRenderer {
+    /// Countdown for the running timer, green while focusing and blue on breaks
+    pub fn render_pomodoro<'a>(pomodoro: &Pomodoro, todo_title: &str) -> Gauge<'a> {
+        let remaining = pomodoro.remaining().as_secs();
+        let color = match pomodoro.phase {
+            Phase::Focus => Color::Green,
+            Phase::Break => Color::Blue,
+        };
+        let mut label = format!(
+            "{} {:02}:{:02} - {} ({} done)",
+            pomodoro.phase.label(),
+            remaining / 60,
+            remaining % 60,
+            todo_title,
+            pomodoro.completed,
+        );
+        if pomodoro.is_paused() {
+            label.push_str(" [paused]");
+        }
+        Gauge::default()
+            .block(Block::default().borders(Borders::ALL).title("Pomodoro").title_bottom("P pause | :pomodoro stop"))
+            .gauge_style(Style::default().fg(color))
+            .ratio(pomodoro.progress().clamp(0.0, 1.0))
+            .label(label)
+    }
+}
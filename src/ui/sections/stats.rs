@@ -0,0 +1,66 @@
+use ratatui::{
+    prelude::*,
+    widgets::{Bar, BarChart, BarGroup, Block, Borders, Paragraph, Sparkline},
+};
+use crate::state::stats::Stats;
+
+pub struct StatsRenderer;
+
+impl StatsRenderer {
+    pub fn render_stats(frame: &mut Frame, area: Rect, stats: &Stats) {
+        let layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(7), Constraint::Min(8), Constraint::Length(6)])
+            .split(area);
+
+        frame.render_widget(Self::summary(stats), layout[0]);
+        frame.render_widget(Self::completed_chart(stats), layout[1]);
+        frame.render_widget(Self::created_sparkline(stats), layout[2]);
+    }
+
+    fn summary(stats: &Stats) -> Paragraph<'static> {
+        let average = match stats.average_completion {
+            Some(duration) if duration.num_days() > 0 => format!("{}d {}h", duration.num_days(), duration.num_hours() % 24),
+            Some(duration) => format!("{}h {}m", duration.num_hours(), duration.num_minutes() % 60),
+            None => "-".to_string(),
+        };
+        let lines = vec![
+            Line::from(format!("Open: {}   Completed: {}", stats.open, stats.completed)),
+            Line::from(vec![
+                Span::styled(format!("Overdue: {}", stats.overdue), Style::default().fg(Color::Red)),
+                Span::raw("   "),
+                Span::styled(format!("Due today: {}", stats.due_today), Style::default().fg(Color::Yellow)),
+            ]),
+            Line::from(format!("Streak: {} day(s)   Longest: {} day(s)", stats.current_streak, stats.longest_streak)),
+            Line::from(format!("Average time to completion: {}", average)),
+        ];
+        Paragraph::new(lines)
+            .block(Block::default().borders(Borders::ALL).title("Stats").title_bottom("s/Esc close"))
+    }
+
+    fn completed_chart(stats: &Stats) -> BarChart<'static> {
+        let bars: Vec<Bar> = stats
+            .completed_per_day
+            .iter()
+            .map(|(day, count)| {
+                Bar::default()
+                    .value(*count)
+                    .label(Line::from(day.format("%d").to_string()))
+                    .style(Style::default().fg(Color::Green))
+            })
+            .collect();
+        BarChart::default()
+            .block(Block::default().borders(Borders::ALL).title("Completed per day"))
+            .data(BarGroup::default().bars(&bars))
+            .bar_width(3)
+            .bar_gap(1)
+    }
+
+    fn created_sparkline(stats: &Stats) -> Sparkline<'static> {
+        let data: Vec<u64> = stats.created_per_day.iter().map(|(_, count)| *count).collect();
+        Sparkline::default()
+            .block(Block::default().borders(Borders::ALL).title("Created per day"))
+            .data(&data)
+            .style(Style::default().fg(Color::Cyan))
+    }
+}
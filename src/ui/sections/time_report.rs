@@ -0,0 +1,60 @@
+use ratatui::{
+    prelude::*,
+    widgets::{Bar, BarChart, BarGroup, Block, Borders, List, ListItem, Paragraph},
+};
+use chrono::Duration;
+use crate::state::timelog::{format_duration, TimeReport};
+
+pub struct TimeReportRenderer;
+
+impl TimeReportRenderer {
+    pub fn render_report(frame: &mut Frame, area: Rect, report: &TimeReport, range: &str) {
+        let layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(3), Constraint::Min(6), Constraint::Length(10)])
+            .split(area);
+        let sums = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+            .split(layout[1]);
+
+        let summary = Paragraph::new(format!("{}   Total: {}", range, format_duration(report.total)))
+            .block(Block::default().borders(Borders::ALL).title("Time report")
+                .title_bottom("Tab range | :report <from> [to] | :report csv <file> | W/Esc close"));
+        frame.render_widget(summary, layout[0]);
+        frame.render_widget(Self::sums("Per todo", &report.per_todo), sums[0]);
+        frame.render_widget(Self::sums("Per tag", &report.per_tag), sums[1]);
+        frame.render_widget(Self::per_day_chart(report), layout[2]);
+    }
+
+    fn sums<'a>(title: &'a str, sums: &[(String, Duration)]) -> List<'a> {
+        let items: Vec<ListItem> = sums
+            .iter()
+            .map(|(name, time)| ListItem::new(Line::from(vec![
+                Span::styled(format!("{:>9}  ", format_duration(*time)), Style::default().fg(Color::Yellow)),
+                Span::raw(name.clone()),
+            ])))
+            .collect();
+        List::new(items).block(Block::default().borders(Borders::ALL).title(title))
+    }
+
+    // Minutes per day; long ranges only show the most recent days that fit
+    fn per_day_chart(report: &TimeReport) -> BarChart<'static> {
+        let bars: Vec<Bar> = report
+            .per_day
+            .iter()
+            .map(|(day, time)| {
+                Bar::default()
+                    .value(time.num_minutes().max(0) as u64)
+                    .text_value(format_duration(*time).split(' ').next().unwrap_or_default().to_string())
+                    .label(Line::from(day.format("%d").to_string()))
+                    .style(Style::default().fg(Color::Blue))
+            })
+            .collect();
+        BarChart::default()
+            .block(Block::default().borders(Borders::ALL).title("Per day"))
+            .data(BarGroup::default().bars(&bars))
+            .bar_width(4)
+            .bar_gap(1)
+    }
+}
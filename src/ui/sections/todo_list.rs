@@ -1,41 +1,230 @@
 use ratatui::{
     prelude::*,
-    widgets::{Block,List,ListItem,Borders,Paragraph},
+    widgets::{Block,Clear,List,ListItem,Borders,Paragraph,Tabs,Wrap},
 };
-use crate::state::todo::Todo;
+use chrono::Local;
+use crate::state::todo::{completion_fractions, Priority, Todo, TodoRow};
 use crate::state::app::InputMode;
+use crate::state::archive::{Bin, RemovedTodo};
+use crate::state::merge::Conflict;
+use crate::state::timelog::format_duration;
 
 pub struct TodoListRenderer;
 
 impl TodoListRenderer {
-    pub fn render_todo_list(todos: &[Todo]) -> List {
-        let items: Vec<ListItem> = todos
+    pub fn render_todo_list<'a>(todos: &'a [Todo], rows: &[TodoRow], title: String, status: Option<&str>, initial_state: &str) -> List<'a> {
+        let today = Local::now().date_naive();
+        // Once per frame rather than a scan of every todo per row
+        let fractions = completion_fractions(todos);
+        let items: Vec<ListItem> = rows
         .iter()
-        .map(|todo| {
-            let content = format!(
-                "{} {}",
-                if todo.completed {"*"} else {" "},
+        .map(|row| {
+            let todo = &todos[row.index];
+            let marker = if !fractions.contains_key(&todo.id) {
+                " "
+            } else if todo.collapsed {
+                "▸"
+            } else {
+                "▾"
+            };
+            let mut spans = vec![Span::raw(format!(
+                "{}{} {} {}",
+                "  ".repeat(row.depth),
+                marker,
+                if todo.is_done() {"*"} else {" "},
                 todo.title
-            );
-            ListItem::new(content)
+            ))];
+            // Open todos past the first step show where they are
+            if !todo.is_done() && todo.state != initial_state {
+                spans.push(Span::styled(format!("  [{}]", todo.state), Style::default().fg(Color::Magenta)));
+            }
+            if let Some((done, total)) = fractions.get(&todo.id) {
+                spans.push(Span::raw(format!("  [{}/{}]", done, total)));
+            }
+            if let Some(project) = &todo.project {
+                spans.push(Span::styled(format!("  +{}", project), Style::default().fg(Color::Cyan)));
+            }
+            for tag in &todo.tags {
+                spans.push(Span::styled(format!(" @{}", tag), Style::default().fg(Color::Green)));
+            }
+            if let Some(priority) = todo.priority {
+                spans.push(Span::raw(format!("  !{}", priority.label())));
+            }
+            if let Some(scheduled) = todo.scheduled {
+                spans.push(Span::raw(format!("  start {}", scheduled.format("%Y-%m-%d"))));
+            }
+            if let Some(due) = todo.due {
+                spans.push(Span::raw(format!("  due {}", due.format("%Y-%m-%d"))));
+            }
+            if let Some(recurrence) = &todo.recurrence {
+                spans.push(Span::raw(format!("  ↻ {}", recurrence)));
+            }
+            if let Some(remind_at) = todo.remind_at.filter(|_| !todo.reminder_dismissed) {
+                spans.push(Span::raw(format!("  ⏰ {}", remind_at.format("%m-%d %H:%M"))));
+            }
+            if !todo.notes.trim().is_empty() {
+                spans.push(Span::raw("  ✎"));
+            }
+            let now = Local::now();
+            match todo.running_timer() {
+                Some(entry) => spans.push(Span::styled(
+                    format!("  ⏱ {}", format_duration(entry.duration(now))),
+                    Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+                )),
+                None if !todo.time_entries.is_empty() => spans.push(Span::styled(
+                    format!("  ⏱ {}", format_duration(todo.tracked_time(now))),
+                    Style::default().fg(Color::DarkGray),
+                )),
+                None => {}
+            }
+            ListItem::new(Line::from(spans)).style(Self::todo_style(todo, today))
         })
         .collect();
+    let mut block = Block::default().borders(Borders::ALL).title(title);
+    if let Some(status) = status {
+        block = block.title_bottom(status.to_string());
+    }
     List::new(items)
-    .block(Block::default().borders(Borders::ALL).title("Todos"))
+    .block(block)
     .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+    .highlight_symbol("> ")
+
+    }
 
+    fn todo_style(todo: &Todo, today: chrono::NaiveDate) -> Style {
+        if todo.is_done() {
+            return Style::default().fg(Color::DarkGray);
+        }
+        let mut style = if todo.is_overdue(today) {
+            Style::default().fg(Color::Red)
+        } else if todo.is_due_today(today) {
+            Style::default().fg(Color::Yellow)
+        } else {
+            Style::default()
+        };
+        if todo.priority == Some(Priority::High) {
+            style = style.add_modifier(Modifier::BOLD);
+            if !todo.is_overdue(today) && !todo.is_due_today(today) {
+                style = style.fg(Color::Magenta);
+            }
+        }
+        style
     }
 
-    pub fn render_input_block(input: &str, input_mode: InputMode) -> Paragraph {
-        Paragraph::new(input.to_string())
+    pub fn render_input_block<'a>(input: &'a str, title: &'a str, input_mode: InputMode, scroll: u16) -> Paragraph<'a> {
+        Paragraph::new(input)
+        .scroll((0, scroll))
         .block(Block::default()
             .borders(Borders::ALL)
-            .title("Add todo")
-            .style(match input_mode {
-                InputMode::Normal => Style::default(),
-                InputMode::Editing => Style::default().fg(Color::Yellow),
-                InputMode::Browser => Style::default(),
-                InputMode::Player => Style::default(),
+            .title(title)
+            .style(if input_mode.is_text_entry() {
+                Style::default().fg(Color::Yellow)
+            } else {
+                Style::default()
             }))
     }
-}
\ No newline at end of file
+
+    /// One tab per list, each with its count of open todos
+    pub fn render_list_tabs<'a>(todos: &[Todo], lists: &'a [String], current: usize) -> Tabs<'a> {
+        let titles: Vec<String> = lists
+        .iter()
+        .map(|list| {
+            let open = todos.iter().filter(|t| &t.list == list && !t.is_done()).count();
+            format!("{} ({})", list, open)
+        })
+        .collect();
+        Tabs::new(titles)
+        .select(current)
+        .highlight_style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))
+        .divider("|")
+    }
+
+    /// Archived or trashed todos, newest last
+    pub fn render_bin<'a>(entries: &'a [RemovedTodo], bin: Bin) -> List<'a> {
+        let items: Vec<ListItem> = entries
+        .iter()
+        .map(|entry| {
+            let todo = &entry.todo;
+            ListItem::new(Line::from(vec![
+                Span::styled(entry.removed_at.format("%Y-%m-%d %H:%M  ").to_string(), Style::default().fg(Color::DarkGray)),
+                Span::raw(format!("{} {}", if todo.is_done() {"*"} else {" "}, todo.title)),
+                Span::styled(format!("  [{}]", todo.list), Style::default().fg(Color::Cyan)),
+            ]))
+        })
+        .collect();
+        List::new(items)
+        .block(Block::default()
+            .borders(Borders::ALL)
+            .title(format!("{} ({})", bin.title(), entries.len()))
+            .title_bottom("r restore | D delete forever | Esc close")
+            .style(Style::default().fg(Color::Yellow)))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .highlight_symbol("> ")
+    }
+
+    /// Modal for a reminder that has come due
+    pub fn render_reminder(frame: &mut Frame, todo: &Todo) {
+        let area = centered_rect(frame.area(), 50, 6);
+        let mut lines = vec![Line::styled(todo.title.clone(), Style::default().add_modifier(Modifier::BOLD))];
+        if let Some(due) = todo.due {
+            lines.push(Line::from(format!("Due {}", due.format("%Y-%m-%d"))));
+        }
+        let prompt = Paragraph::new(lines)
+            .wrap(Wrap { trim: true })
+            .block(Block::default().borders(Borders::ALL).title("Reminder")
+                .title_bottom("Enter dismiss | z snooze")
+                .style(Style::default().fg(Color::Magenta)));
+        frame.render_widget(Clear, area);
+        frame.render_widget(prompt, area);
+    }
+
+    /// One-line warning shown while the store may not be written
+    pub fn render_read_only_banner<'a>(reason: &str) -> Paragraph<'a> {
+        Paragraph::new(format!(" READ-ONLY: {}; changes will not be saved", reason))
+        .style(Style::default().fg(Color::Black).bg(Color::Red).add_modifier(Modifier::BOLD))
+    }
+
+    /// Both versions of a todo that was changed here and outside the app
+    pub fn render_conflict(frame: &mut Frame, conflict: &Conflict, remaining: usize) {
+        let area = centered_rect(frame.area(), 70, 8);
+        let describe = |todo: &Option<Todo>| match todo {
+            Some(todo) => format!("{}{}", if todo.is_done() {"[x] "} else {"[ ] "}, todo.to_input()),
+            None => "(deleted)".to_string(),
+        };
+        let lines = vec![
+            Line::from(format!("Todo #{} was changed here and outside the app:", conflict.id())),
+            Line::from(""),
+            Line::from(vec![Span::styled("Mine:   ", Style::default().add_modifier(Modifier::BOLD)), Span::raw(describe(&conflict.ours))]),
+            Line::from(vec![Span::styled("Theirs: ", Style::default().add_modifier(Modifier::BOLD)), Span::raw(describe(&conflict.theirs))]),
+        ];
+        let prompt = Paragraph::new(lines)
+            .wrap(Wrap { trim: true })
+            .block(Block::default().borders(Borders::ALL).title(format!("Conflict (1 of {})", remaining))
+                .title_bottom("m keep mine | t take theirs")
+                .style(Style::default().fg(Color::Red)));
+        frame.render_widget(Clear, area);
+        frame.render_widget(prompt, area);
+    }
+
+    /// Small centered yes/no prompt drawn over the list
+    pub fn render_confirm(frame: &mut Frame, message: &str) {
+        let area = centered_rect(frame.area(), 50, 5);
+        let prompt = Paragraph::new(message.to_string())
+            .wrap(Wrap { trim: true })
+            .block(Block::default().borders(Borders::ALL).title("Confirm")
+                .style(Style::default().fg(Color::Yellow)));
+        frame.render_widget(Clear, area);
+        frame.render_widget(prompt, area);
+    }
+}
+
+pub fn centered_rect(area: Rect, percent_x: u16, height: u16) -> Rect {
+    let width = area.width * percent_x / 100;
+    let height = height.min(area.height);
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    }
+}
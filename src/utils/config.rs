@@ -0,0 +1,195 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+/// User settings read from `config.yaml`. Every field has a default, so a
+/// missing file or a partial one is fine.
+#[derive(Deserialize, Default, Debug)]
+#[serde(default)]
+pub struct Config {
+    pub storage: StorageConfig,
+    pub archive: ArchiveConfig,
+    pub pomodoro: PomodoroConfig,
+    pub reminders: ReminderConfig,
+    pub workflow: WorkflowConfig,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct WorkflowConfig {
+    /// Open states in board order; `Done` always comes after them
+    pub states: Vec<String>,
+    /// State new and reopened todos start in
+    pub initial: String,
+}
+
+impl Default for WorkflowConfig {
+    fn default() -> Self {
+        Self {
+            states: ["Backlog", "Todo", "In Progress", "Blocked"].map(String::from).to_vec(),
+            initial: "Todo".to_string(),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct ReminderConfig {
+    /// Ring the terminal bell when a reminder fires
+    pub bell: bool,
+    /// Desktop notification escape sequence to emit, if any
+    pub notify: Option<NotifyEscape>,
+    pub snooze_minutes: i64,
+}
+
+impl Default for ReminderConfig {
+    fn default() -> Self {
+        Self { bell: true, notify: None, snooze_minutes: 10 }
+    }
+}
+
+/// Terminal escapes that many emulators turn into desktop notifications
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum NotifyEscape {
+    /// `OSC 9`, understood by iTerm2, kitty, WezTerm and Windows Terminal
+    Osc9,
+    /// `OSC 777`, understood by urxvt and VTE-based terminals
+    Osc777,
+}
+
+#[derive(Deserialize, Default, Debug)]
+#[serde(default)]
+pub struct ArchiveConfig {
+    /// Completed todos older than this are archived at startup
+    pub after_days: Option<u32>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct PomodoroConfig {
+    pub focus_minutes: u64,
+    pub break_minutes: u64,
+    /// Audio file, folder or `.m3u` playlist played during focus time
+    pub music: Option<PathBuf>,
+    pub on_break: BreakMusic,
+    /// Volume used when `on_break` is `lower`, from 0.0 to 1.0
+    pub break_volume: f32,
+}
+
+impl Default for PomodoroConfig {
+    fn default() -> Self {
+        Self {
+            focus_minutes: 25,
+            break_minutes: 5,
+            music: None,
+            on_break: BreakMusic::Pause,
+            break_volume: 0.2,
+        }
+    }
+}
+
+/// What happens to the music while on a break
+#[derive(Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum BreakMusic {
+    #[default]
+    Pause,
+    Lower,
+    Keep,
+}
+
+#[derive(Deserialize, Default, Debug)]
+#[serde(default)]
+pub struct StorageConfig {
+    pub backend: Backend,
+    /// Where the store lives; defaults to the data directory
+    pub path: Option<PathBuf>,
+    /// What to do when another instance already has the store open
+    pub on_locked: OnLocked,
+}
+
+#[derive(Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum OnLocked {
+    /// Open anyway, but never write to the store
+    #[default]
+    ReadOnly,
+    /// Exit with an error
+    Refuse,
+}
+
+#[derive(Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    #[default]
+    Json,
+    Yaml,
+    /// Needs the `sqlite` feature, which is on by default
+    Sqlite,
+}
+
+impl Backend {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Backend::Json => "json",
+            Backend::Yaml => "yaml",
+            Backend::Sqlite => "db",
+        }
+    }
+}
+
+impl Config {
+    /// Reads `$TODO_CONFIG`, or `config.yaml` in the XDG config directory
+    pub fn load() -> Result<Config> {
+        let path = match env::var_os("TODO_CONFIG") {
+            Some(path) => PathBuf::from(path),
+            None => match config_dir() {
+                Some(dir) => dir.join("config.yaml"),
+                None => return Ok(Config::default()),
+            },
+        };
+        if !path.exists() {
+            return Ok(Config::default());
+        }
+        let yaml = fs::read_to_string(&path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        serde_yaml::from_str(&yaml)
+            .with_context(|| format!("failed to parse {}", path.display()))
+    }
+
+    /// The configured store path, or `todos.<ext>` in the data directory
+    pub fn storage_path(&self) -> PathBuf {
+        if let Some(path) = &self.storage.path {
+            return path.clone();
+        }
+        let file_name = format!("todos.{}", self.storage.backend.extension());
+        match data_dir() {
+            Some(dir) => dir.join(file_name),
+            None => PathBuf::from(file_name),
+        }
+    }
+
+    /// True when the store path comes from the defaults rather than the file
+    pub fn uses_default_path(&self) -> bool {
+        self.storage.path.is_none()
+    }
+}
+
+fn config_dir() -> Option<PathBuf> {
+    xdg_dir("XDG_CONFIG_HOME", ".config")
+}
+
+fn data_dir() -> Option<PathBuf> {
+    xdg_dir("XDG_DATA_HOME", ".local/share")
+}
+
+fn xdg_dir(variable: &str, fallback: &str) -> Option<PathBuf> {
+    let base = match env::var_os(variable) {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(env::var_os("HOME")?).join(fallback),
+    };
+    Some(base.join("todo"))
+}
@@ -0,0 +1,42 @@
+use anyhow::{bail, Context, Result};
+use crossterm::{
+    event::{DisableBracketedPaste, EnableBracketedPaste},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use std::env;
+use std::fs;
+use std::io::stdout;
+use std::process::Command;
+
+/// Leaves the TUI, opens `$VISUAL` / `$EDITOR` (falling back to `vi`) on a
+/// temp Markdown file holding `text`, and returns what was saved.
+pub fn edit_text(text: &str) -> Result<String> {
+    let editor = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    let file = tempfile::Builder::new()
+        .prefix("todo-notes-")
+        .suffix(".md")
+        .tempfile()
+        .context("failed to create temp file")?;
+    fs::write(file.path(), text).context("failed to write temp file")?;
+
+    disable_raw_mode()?;
+    execute!(stdout(), DisableBracketedPaste, LeaveAlternateScreen)?;
+    // The editor command may carry arguments, e.g. `code --wait`
+    let mut words = editor.split_whitespace();
+    let status = Command::new(words.next().unwrap_or("vi"))
+        .args(words)
+        .arg(file.path())
+        .status();
+    enable_raw_mode()?;
+    execute!(stdout(), EnterAlternateScreen, EnableBracketedPaste)?;
+
+    let status = status.with_context(|| format!("failed to run {}", editor))?;
+    if !status.success() {
+        bail!("{} exited with {}", editor, status);
+    }
+    let edited = fs::read_to_string(file.path()).context("failed to read temp file")?;
+    Ok(edited.trim_end().to_string())
+}
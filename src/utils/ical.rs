@@ -0,0 +1,392 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use chrono::Weekday;
+
+use crate::state::todo::{Priority, Recurrence, Todo};
+use crate::state::workflow::{Workflow, DONE_STATE};
+
+/// `Todo::attributes` key holding the uid of an imported VTODO
+const UID_ATTRIBUTE: &str = "ical-uid";
+
+/// Custom property with the exact workflow state of an open todo, which
+/// `STATUS` can only approximate
+const STATE_PROPERTY: &str = "X-STATE";
+
+/// Reads the `VTODO` components of an iCalendar (RFC 5545) file
+pub fn read(path: &Path, workflow: &Workflow) -> Result<Vec<Todo>> {
+    let contents = fs::read_to_string(path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    Ok(parse(&contents, workflow))
+}
+
+pub fn write(todos: &[Todo], path: &Path, workflow: &Workflow) -> Result<()> {
+    fs::write(path, format(todos, workflow))
+        .with_context(|| format!("failed to write {}", path.display()))
+}
+
+/// `STATUS` maps onto `workflow`: `IN-PROCESS` to its in-progress state and
+/// anything open to its initial one, unless `X-STATE` names one of its states
+pub fn parse(contents: &str, workflow: &Workflow) -> Vec<Todo> {
+    let mut todos = Vec::new();
+    let mut uids: HashMap<String, usize> = HashMap::new();
+    // Parent uids, resolved once every uid is known
+    let mut related: Vec<(usize, String)> = Vec::new();
+    let mut current: Option<Todo> = None;
+    // Components such as VALARM opened inside the current VTODO; their
+    // properties are not the todo's
+    let mut nested = 0;
+    // `X-STATE` of the current VTODO, applied once its `STATUS` is known too
+    let mut state: Option<String> = None;
+
+    for line in unfold(contents) {
+        let Some((name, params, value)) = split_property(&line) else {
+            continue;
+        };
+        match (name.as_str(), current.as_mut()) {
+            ("BEGIN", None) if value == "VTODO" => {
+                let mut todo = Todo::new(todos.len() + 1, String::new());
+                todo.state = workflow.initial().to_string();
+                current = Some(todo);
+                nested = 0;
+                state = None;
+            }
+            ("BEGIN", Some(_)) => nested += 1,
+            ("END", Some(_)) if nested > 0 => nested -= 1,
+            ("END", Some(todo)) if value == "VTODO" => {
+                if let Some(state) = state.take().filter(|s| !todo.is_done() && workflow.states().contains(s)) {
+                    todo.state = state;
+                }
+                todos.extend(current.take());
+            }
+            (_, Some(_)) if nested > 0 => {}
+            (_, Some(todo)) => match name.as_str() {
+                "UID" => {
+                    uids.insert(value.to_string(), todo.id);
+                    todo.attributes.insert(UID_ATTRIBUTE.to_string(), value.to_string());
+                }
+                "SUMMARY" => todo.title = unescape(value),
+                "DESCRIPTION" => todo.notes = unescape(value),
+                "STATUS" => {
+                    todo.state = match value.trim().to_ascii_uppercase().as_str() {
+                        "COMPLETED" => DONE_STATE,
+                        "IN-PROCESS" => workflow.in_progress(),
+                        _ => workflow.initial(),
+                    }
+                    .to_string();
+                }
+                STATE_PROPERTY => state = Some(unescape(value)),
+                "COMPLETED" => todo.completed_at = parse_date_time(value),
+                "CREATED" => {
+                    if let Some(created) = parse_date_time(value) {
+                        todo.created_at = created;
+                    }
+                }
+                "DUE" => todo.due = parse_date(value, &params),
+                "DTSTART" => todo.scheduled = parse_date(value, &params),
+                "PRIORITY" => {
+                    todo.priority = match value.trim().parse::<u8>() {
+                        Ok(1..=4) => Some(Priority::High),
+                        Ok(5) => Some(Priority::Medium),
+                        Ok(6..=9) => Some(Priority::Low),
+                        _ => None,
+                    }
+                }
+                "CATEGORIES" => {
+                    for tag in split_list(value) {
+                        if !todo.tags.contains(&tag) {
+                            todo.tags.push(tag);
+                        }
+                    }
+                }
+                "X-PROJECT" => todo.project = Some(unescape(value)),
+                "RELATED-TO" => related.push((todo.id, value.to_string())),
+                "RRULE" => todo.recurrence = parse_rrule(value),
+                "X-RECUR-AFTER-COMPLETION" => {
+                    todo.recurrence = value.trim().parse().ok().map(Recurrence::AfterCompletion);
+                }
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+
+    for (id, parent_uid) in related {
+        if let (Some(parent_id), Some(todo)) = (uids.get(&parent_uid), todos.iter_mut().find(|t| t.id == id)) {
+            todo.parent_id = Some(*parent_id);
+        }
+    }
+    // Completed without a timestamp still counts as completed
+    for todo in todos.iter_mut().filter(|t| t.is_done() && t.completed_at.is_none()) {
+        todo.completed_at = Some(Local::now());
+    }
+    todos
+}
+
+/// Open todos in any state of `workflow` but the initial one are `IN-PROCESS`
+pub fn format(todos: &[Todo], workflow: &Workflow) -> String {
+    let now = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//rust_tui//todo//EN".to_string(),
+    ];
+    for todo in todos {
+        lines.push("BEGIN:VTODO".to_string());
+        lines.push(format!("UID:{}", uid(todo)));
+        lines.push(format!("DTSTAMP:{}", now));
+        lines.push(format!("CREATED:{}", format_date_time(todo.created_at)));
+        lines.push(format!("SUMMARY:{}", escape(&todo.title)));
+        if !todo.notes.is_empty() {
+            lines.push(format!("DESCRIPTION:{}", escape(&todo.notes)));
+        }
+        if todo.is_done() {
+            lines.push("STATUS:COMPLETED".to_string());
+            if let Some(completed_at) = todo.completed_at {
+                lines.push(format!("COMPLETED:{}", format_date_time(completed_at)));
+            }
+        } else if todo.state == workflow.initial() {
+            lines.push("STATUS:NEEDS-ACTION".to_string());
+        } else {
+            lines.push("STATUS:IN-PROCESS".to_string());
+            lines.push(format!("{}:{}", STATE_PROPERTY, escape(&todo.state)));
+        }
+        if let Some(priority) = todo.priority {
+            let value = match priority {
+                Priority::High => 1,
+                Priority::Medium => 5,
+                Priority::Low => 9,
+            };
+            lines.push(format!("PRIORITY:{}", value));
+        }
+        if let Some(due) = todo.due {
+            lines.push(format!("DUE;VALUE=DATE:{}", due.format("%Y%m%d")));
+        }
+        if let Some(scheduled) = todo.scheduled {
+            lines.push(format!("DTSTART;VALUE=DATE:{}", scheduled.format("%Y%m%d")));
+        }
+        if !todo.tags.is_empty() {
+            let tags: Vec<String> = todo.tags.iter().map(|t| escape(t)).collect();
+            lines.push(format!("CATEGORIES:{}", tags.join(",")));
+        }
+        if let Some(project) = &todo.project {
+            lines.push(format!("X-PROJECT:{}", escape(project)));
+        }
+        match &todo.recurrence {
+            Some(Recurrence::AfterCompletion(days)) => {
+                // Has no RRULE equivalent
+                lines.push(format!("X-RECUR-AFTER-COMPLETION:{}", days));
+            }
+            Some(recurrence) => lines.extend(format_rrule(recurrence).map(|rule| format!("RRULE:{}", rule))),
+            None => {}
+        }
+        if let Some(parent) = todo.parent_id.and_then(|id| todos.iter().find(|t| t.id == id)) {
+            lines.push(format!("RELATED-TO:{}", uid(parent)));
+        }
+        lines.push("END:VTODO".to_string());
+    }
+    lines.push("END:VCALENDAR".to_string());
+
+    let mut contents = String::new();
+    for line in lines {
+        contents.push_str(&fold(&line));
+    }
+    contents
+}
+
+// Stable across exports so calendar apps update rather than duplicate;
+// imported todos keep the uid they came with
+fn uid(todo: &Todo) -> String {
+    match todo.attributes.get(UID_ATTRIBUTE) {
+        Some(uid) => uid.clone(),
+        None => format!("todo-{}-{}@rust_tui", todo.id, todo.created_at.timestamp()),
+    }
+}
+
+fn format_rrule(recurrence: &Recurrence) -> Option<String> {
+    match recurrence {
+        Recurrence::Daily => Some("FREQ=DAILY".to_string()),
+        Recurrence::Weekly(days) if days.is_empty() => Some("FREQ=WEEKLY".to_string()),
+        Recurrence::Weekly(days) => {
+            let days: Vec<&str> = days.iter().map(|d| weekday_code(*d)).collect();
+            Some(format!("FREQ=WEEKLY;BYDAY={}", days.join(",")))
+        }
+        Recurrence::Monthly(day) => Some(format!("FREQ=MONTHLY;BYMONTHDAY={}", day)),
+        Recurrence::AfterCompletion(_) => None,
+    }
+}
+
+// Only the rules `Recurrence` can express; anything else is dropped
+fn parse_rrule(value: &str) -> Option<Recurrence> {
+    let parts: HashMap<&str, &str> = value
+        .split(';')
+        .filter_map(|part| part.split_once('='))
+        .collect();
+    match parts.get("FREQ").copied()? {
+        "DAILY" => Some(Recurrence::Daily),
+        "WEEKLY" => {
+            let days = parts
+                .get("BYDAY")
+                .map(|days| days.split(',').filter_map(parse_weekday_code).collect())
+                .unwrap_or_default();
+            Some(Recurrence::Weekly(days))
+        }
+        "MONTHLY" => parts
+            .get("BYMONTHDAY")
+            .and_then(|day| day.parse().ok())
+            .map(Recurrence::Monthly),
+        _ => None,
+    }
+}
+
+fn weekday_code(day: Weekday) -> &'static str {
+    match day {
+        Weekday::Mon => "MO",
+        Weekday::Tue => "TU",
+        Weekday::Wed => "WE",
+        Weekday::Thu => "TH",
+        Weekday::Fri => "FR",
+        Weekday::Sat => "SA",
+        Weekday::Sun => "SU",
+    }
+}
+
+fn parse_weekday_code(code: &str) -> Option<Weekday> {
+    match code {
+        "MO" => Some(Weekday::Mon),
+        "TU" => Some(Weekday::Tue),
+        "WE" => Some(Weekday::Wed),
+        "TH" => Some(Weekday::Thu),
+        "FR" => Some(Weekday::Fri),
+        "SA" => Some(Weekday::Sat),
+        "SU" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+/// Joins continuation lines (those starting with a space or tab)
+fn unfold(contents: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in contents.lines() {
+        let line = line.trim_end_matches('\r');
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(previous)) => previous.push_str(rest),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+/// Splits lines longer than 75 octets, ending each with CRLF
+fn fold(line: &str) -> String {
+    let mut folded = String::new();
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            width = 1;
+        }
+        folded.push(c);
+        width += c.len_utf8();
+    }
+    folded.push_str("\r\n");
+    folded
+}
+
+// `NAME;PARAM=x:value` -> (NAME, [PARAM=x], value). Parameter values may be
+// quoted, as in `ALTREP="http://..."`, and hide `:` and `;` in the quotes.
+fn split_property(line: &str) -> Option<(String, Vec<String>, &str)> {
+    let mut quoted = false;
+    let mut separators = Vec::new();
+    let mut colon = None;
+    for (index, c) in line.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            ';' if !quoted => separators.push(index),
+            ':' if !quoted => {
+                colon = Some(index);
+                break;
+            }
+            _ => {}
+        }
+    }
+    let colon = colon?;
+    let (head, value) = (&line[..colon], &line[colon + 1..]);
+    let mut bounds = separators;
+    bounds.push(colon);
+    let name = head[..bounds[0]].to_ascii_uppercase();
+    let params = bounds.windows(2).map(|w| line[w[0] + 1..w[1]].to_ascii_uppercase()).collect();
+    Some((name, params, value))
+}
+
+fn parse_date(value: &str, params: &[String]) -> Option<NaiveDate> {
+    if params.iter().any(|p| p == "VALUE=DATE") || value.len() == 8 {
+        return NaiveDate::parse_from_str(value, "%Y%m%d").ok();
+    }
+    parse_date_time(value).map(|dt| dt.date_naive())
+}
+
+fn parse_date_time(value: &str) -> Option<DateTime<Local>> {
+    if let Some(utc) = value.strip_suffix('Z') {
+        let naive = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?;
+        return Some(Utc.from_utc_datetime(&naive).with_timezone(&Local));
+    }
+    let naive = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?;
+    Local.from_local_datetime(&naive).earliest()
+}
+
+fn format_date_time(value: DateTime<Local>) -> String {
+    value.with_timezone(&Utc).format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+fn unescape(text: &str) -> String {
+    let mut result = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('n') | Some('N') => result.push('\n'),
+                Some(other) => result.push(other),
+                None => {}
+            }
+        } else {
+            result.push(c);
+        }
+    }
+    result
+}
+
+// Splits on unescaped commas
+fn split_list(value: &str) -> Vec<String> {
+    let mut items = Vec::new();
+    let mut current = String::new();
+    let mut escaped = false;
+    for c in value.chars() {
+        match c {
+            _ if escaped => {
+                current.push('\\');
+                current.push(c);
+                escaped = false;
+            }
+            '\\' => escaped = true,
+            ',' => items.push(std::mem::take(&mut current)),
+            _ => current.push(c),
+        }
+    }
+    items.push(current);
+    items
+        .iter()
+        .map(|item| unescape(item.trim()))
+        .filter(|item| !item.is_empty())
+        .collect()
+}
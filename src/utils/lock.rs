@@ -0,0 +1,191 @@
+use anyhow::{Context, Result};
+use std::fmt;
+use std::fs;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::process::{self, Command};
+use std::thread;
+use std::time::Duration;
+use tempfile::NamedTempFile;
+
+/// How long an unreadable lock file counts as still being written, by a
+/// version that filled the file in after creating it
+const WRITE_GRACE: Duration = Duration::from_secs(3);
+/// Pause before looking at such a file again
+const RETRY_DELAY: Duration = Duration::from_millis(100);
+
+/// The instance holding a lock, as written in the lock file
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LockOwner {
+    pub pid: u32,
+    pub host: String,
+}
+
+impl LockOwner {
+    fn current() -> Self {
+        Self { pid: process::id(), host: hostname() }
+    }
+
+    fn parse(contents: &str) -> Option<Self> {
+        let mut lines = contents.lines();
+        let pid = lines.next()?.trim().parse().ok()?;
+        let host = lines.next()?.trim().to_string();
+        Some(Self { pid, host })
+    }
+
+    // Only processes on this machine can be checked; a lock from another
+    // host counts as live
+    fn is_alive(&self) -> bool {
+        self.host != hostname() || process_alive(self.pid)
+    }
+}
+
+impl fmt::Display for LockOwner {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "PID {} on {}", self.pid, self.host)
+    }
+}
+
+/// Advisory lock marking a store as written by one running instance. The
+/// lock file is removed when this is dropped.
+#[derive(Debug)]
+pub struct StoreLock {
+    path: PathBuf,
+}
+
+/// Outcome of trying to lock a store
+#[derive(Debug)]
+pub enum LockState {
+    /// Only kept so the lock is released when dropped
+    Acquired(#[allow(dead_code)] StoreLock),
+    /// Another live instance has it
+    HeldBy(LockOwner),
+}
+
+impl StoreLock {
+    /// `todos.json` is locked by `todos.json.lock`
+    pub fn path_for(store: &Path) -> PathBuf {
+        let mut name = store.file_name().unwrap_or_default().to_os_string();
+        name.push(".lock");
+        store.with_file_name(name)
+    }
+
+    /// Takes the lock for `store`. A lock left behind by a process that is no
+    /// longer running on this host is removed and taken over.
+    pub fn acquire(store: &Path) -> Result<LockState> {
+        let path = Self::path_for(store);
+        let owner = LockOwner::current();
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        // Every retry follows the lock changing hands, or waits out a lock
+        // file still being written
+        let attempts = (WRITE_GRACE.as_millis() / RETRY_DELAY.as_millis()) as usize + 2;
+        for _ in 0..attempts {
+            // Filled in before it appears under the lock name, so no one ever
+            // reads our lock half-written
+            let mut temp = NamedTempFile::new_in(dir)
+                .with_context(|| format!("failed to create a lock file in {}", dir.display()))?;
+            writeln!(temp, "{}\n{}", owner.pid, owner.host)
+                .with_context(|| format!("failed to write {}", temp.path().display()))?;
+            match temp.persist_noclobber(&path) {
+                Ok(_) => return Ok(LockState::Acquired(StoreLock { path })),
+                Err(e) if e.error.kind() == ErrorKind::AlreadyExists => {}
+                Err(e) => return Err(e.error).with_context(|| format!("failed to create {}", path.display())),
+            }
+            let contents = match fs::read_to_string(&path) {
+                Ok(contents) => contents,
+                // Released in the meantime
+                Err(e) if e.kind() == ErrorKind::NotFound => continue,
+                Err(e) => return Err(e).with_context(|| format!("failed to read {}", path.display())),
+            };
+            match LockOwner::parse(&contents) {
+                Some(holder) if holder.is_alive() => return Ok(LockState::HeldBy(holder)),
+                None if is_recent(&path) => thread::sleep(RETRY_DELAY),
+                // Dead owner, or a garbled file nobody is writing any more
+                _ => Self::remove_stale(&path, &contents, dir)?,
+            }
+        }
+        anyhow::bail!("could not lock {}: {} keeps changing", store.display(), path.display())
+    }
+
+    // Removes the lock file read as `contents`. Another instance may have
+    // replaced it since, so it is first moved onto a temp file of our own and
+    // only deleted if it is still the stale one; a live lock moved by
+    // mistake is put back.
+    fn remove_stale(path: &Path, contents: &str, dir: &Path) -> Result<()> {
+        let aside = NamedTempFile::new_in(dir)
+            .with_context(|| format!("failed to create a temp file in {}", dir.display()))?
+            .into_temp_path();
+        match fs::rename(path, &aside) {
+            Ok(()) => {}
+            // Someone else cleared it first
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e).with_context(|| format!("failed to remove stale {}", path.display())),
+        }
+        if fs::read_to_string(&aside).ok().as_deref() != Some(contents) {
+            // Fails harmlessly if yet another instance has locked it meanwhile
+            let _ = fs::hard_link(&aside, path);
+        }
+        aside.close().with_context(|| format!("failed to remove stale {}", path.display()))
+    }
+}
+
+// Whether the file was modified within `WRITE_GRACE`; a clock that went
+// backwards counts as recent
+fn is_recent(path: &Path) -> bool {
+    fs::metadata(path)
+        .and_then(|m| m.modified())
+        .map(|modified| modified.elapsed().map_or(true, |age| age < WRITE_GRACE))
+        .unwrap_or(false)
+}
+
+impl Drop for StoreLock {
+    fn drop(&mut self) {
+        // Leave the file alone if someone else took it over meanwhile
+        let ours = fs::read_to_string(&self.path)
+            .ok()
+            .and_then(|contents| LockOwner::parse(&contents))
+            .is_some_and(|owner| owner == LockOwner::current());
+        if ours {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+fn hostname() -> String {
+    let name = fs::read_to_string("/etc/hostname")
+        .ok()
+        .or_else(|| Command::new("hostname").output().ok().and_then(|o| String::from_utf8(o.stdout).ok()))
+        .or_else(|| std::env::var("COMPUTERNAME").ok())
+        .unwrap_or_default();
+    match name.trim() {
+        "" => "localhost".to_string(),
+        name => name.to_string(),
+    }
+}
+
+#[cfg(unix)]
+fn process_alive(pid: u32) -> bool {
+    if pid == process::id() {
+        return true;
+    }
+    // 0 and negative numbers would address process groups
+    let pid = match libc::pid_t::try_from(pid) {
+        Ok(pid) if pid > 0 => pid,
+        _ => return false,
+    };
+    // Signal 0 checks the process exists without signalling it. EPERM means
+    // it exists but belongs to another user; only ESRCH means it is gone.
+    // SAFETY: sending signal 0 has no effect on the target process
+    if unsafe { libc::kill(pid, 0) } == 0 {
+        return true;
+    }
+    std::io::Error::last_os_error().raw_os_error() != Some(libc::ESRCH)
+}
+
+#[cfg(not(unix))]
+fn process_alive(_pid: u32) -> bool {
+    true
+}
@@ -0,0 +1,71 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::Path;
+
+use crate::state::todo::{all_rows, Todo};
+
+/// Reads a GitHub-flavored Markdown task list. Nesting by indentation
+/// becomes subtasks; lines that are not `- [ ]`/`- [x]` items are skipped.
+pub fn read(path: &Path) -> Result<Vec<Todo>> {
+    let contents = fs::read_to_string(path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    Ok(parse(&contents))
+}
+
+pub fn write(todos: &[Todo], path: &Path) -> Result<()> {
+    fs::write(path, format(todos))
+        .with_context(|| format!("failed to write {}", path.display()))
+}
+
+pub fn parse(contents: &str) -> Vec<Todo> {
+    let mut todos = Vec::new();
+    // (indent, id) of the items enclosing the current line
+    let mut parents: Vec<(usize, usize)> = Vec::new();
+    for line in contents.lines() {
+        let indent = line.len() - line.trim_start().len();
+        let Some((done, text)) = parse_task(line.trim_start()) else {
+            continue;
+        };
+        while parents.last().is_some_and(|&(parent_indent, _)| parent_indent >= indent) {
+            parents.pop();
+        }
+        let mut todo = Todo::from_input(todos.len() + 1, text);
+        todo.parent_id = parents.last().map(|&(_, id)| id);
+        if done {
+            todo.toggle_complete();
+        }
+        parents.push((indent, todo.id));
+        todos.push(todo);
+    }
+    todos
+}
+
+pub fn format(todos: &[Todo]) -> String {
+    let mut contents = String::new();
+    for row in all_rows(todos) {
+        let todo = &todos[row.index];
+        contents.push_str(&format!(
+            "{}- [{}] {}\n",
+            "  ".repeat(row.depth),
+            if todo.is_done() { "x" } else { " " },
+            todo.to_input()
+        ));
+    }
+    contents
+}
+
+// `- [ ] text`, also with `*` or `+` bullets
+fn parse_task(line: &str) -> Option<(bool, &str)> {
+    let rest = line
+        .strip_prefix("- ")
+        .or_else(|| line.strip_prefix("* "))
+        .or_else(|| line.strip_prefix("+ "))?;
+    let (done, text) = if let Some(text) = rest.strip_prefix("[ ]") {
+        (false, text)
+    } else if let Some(text) = rest.strip_prefix("[x]").or_else(|| rest.strip_prefix("[X]")) {
+        (true, text)
+    } else {
+        return None;
+    };
+    Some((done, text.trim()))
+}
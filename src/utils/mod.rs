@@ -1,2 +1,11 @@
 pub mod storage;
-pub mod art;
\ No newline at end of file
+pub mod art;
+pub mod config;
+pub mod todotxt;
+pub mod markdown;
+pub mod org;
+pub mod ical;
+pub mod editor;
+pub mod lock;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
@@ -0,0 +1,168 @@
+use anyhow::{Context, Result};
+use chrono::{Local, NaiveDateTime, TimeZone};
+use std::fs;
+use std::path::Path;
+
+use crate::state::todo::{all_rows, Priority, Todo};
+
+/// Reads Org-mode `TODO`/`DONE` headlines. Headline depth becomes subtask
+/// nesting; planning lines and a `:PROJECT:` property are picked up too.
+pub fn read(path: &Path) -> Result<Vec<Todo>> {
+    let contents = fs::read_to_string(path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    Ok(parse(&contents))
+}
+
+pub fn write(todos: &[Todo], path: &Path) -> Result<()> {
+    fs::write(path, format(todos))
+        .with_context(|| format!("failed to write {}", path.display()))
+}
+
+pub fn parse(contents: &str) -> Vec<Todo> {
+    let mut todos: Vec<Todo> = Vec::new();
+    // (level, id) of the headlines enclosing the current one
+    let mut parents: Vec<(usize, usize)> = Vec::new();
+    // Whether body lines currently belong to the last todo
+    let mut in_todo = false;
+    for line in contents.lines() {
+        let level = line.chars().take_while(|&c| c == '*').count();
+        if level > 0 && line[level..].starts_with(' ') {
+            while parents.last().is_some_and(|&(parent_level, _)| parent_level >= level) {
+                parents.pop();
+            }
+            in_todo = false;
+            if let Some(mut todo) = parse_headline(todos.len() + 1, line[level..].trim()) {
+                todo.parent_id = parents.last().map(|&(_, id)| id);
+                parents.push((level, todo.id));
+                todos.push(todo);
+                in_todo = true;
+            }
+            continue;
+        }
+        if let (true, Some(todo)) = (in_todo, todos.last_mut()) {
+            parse_body_line(todo, line.trim());
+        }
+    }
+    todos
+}
+
+pub fn format(todos: &[Todo]) -> String {
+    let mut contents = String::new();
+    for row in all_rows(todos) {
+        let todo = &todos[row.index];
+        let indent = " ".repeat(row.depth + 2);
+        let mut headline = format!(
+            "{} {}",
+            "*".repeat(row.depth + 1),
+            if todo.is_done() { "DONE" } else { "TODO" }
+        );
+        if let Some(priority) = todo.priority {
+            headline.push_str(&format!(" [#{}]", priority_letter(priority)));
+        }
+        headline.push(' ');
+        headline.push_str(&todo.title);
+        if !todo.tags.is_empty() {
+            headline.push_str(&format!(" :{}:", todo.tags.join(":")));
+        }
+        contents.push_str(&headline);
+        contents.push('\n');
+
+        let mut planning = Vec::new();
+        if let Some(completed_at) = todo.completed_at {
+            planning.push(format!("CLOSED: [{}]", completed_at.format("%Y-%m-%d %a %H:%M")));
+        }
+        if let Some(due) = todo.due {
+            planning.push(format!("DEADLINE: <{}>", due.format("%Y-%m-%d %a")));
+        }
+        if let Some(scheduled) = todo.scheduled {
+            planning.push(format!("SCHEDULED: <{}>", scheduled.format("%Y-%m-%d %a")));
+        }
+        if !planning.is_empty() {
+            contents.push_str(&format!("{}{}\n", indent, planning.join(" ")));
+        }
+        if let Some(project) = &todo.project {
+            contents.push_str(&format!("{}:PROPERTIES:\n{}:PROJECT: {}\n{}:END:\n", indent, indent, project, indent));
+        }
+    }
+    contents
+}
+
+// `TODO [#A] Title :tag:other:` without the leading stars
+fn parse_headline(id: usize, headline: &str) -> Option<Todo> {
+    let (completed, rest) = if let Some(rest) = headline.strip_prefix("TODO ") {
+        (false, rest)
+    } else if let Some(rest) = headline.strip_prefix("DONE ") {
+        (true, rest)
+    } else {
+        return None;
+    };
+    let mut todo = Todo::new(id, String::new());
+    todo.set_done(completed);
+
+    let mut rest = rest.trim();
+    if let Some(after) = rest.strip_prefix("[#") {
+        if let Some((letter, title)) = after.split_once(']') {
+            todo.priority = match letter {
+                "A" => Some(Priority::High),
+                "B" => Some(Priority::Medium),
+                "C" => Some(Priority::Low),
+                _ => None,
+            };
+            rest = title.trim();
+        }
+    }
+    if let Some((title, tags)) = rest.rsplit_once(' ') {
+        if tags.len() > 2 && tags.starts_with(':') && tags.ends_with(':') {
+            todo.tags = tags
+                .trim_matches(':')
+                .split(':')
+                .filter(|t| !t.is_empty())
+                .map(String::from)
+                .collect();
+            rest = title.trim();
+        }
+    }
+    todo.title = rest.to_string();
+    if completed {
+        todo.completed_at = Some(Local::now());
+    }
+    Some(todo)
+}
+
+fn parse_body_line(todo: &mut Todo, line: &str) {
+    if let Some(project) = line.strip_prefix(":PROJECT:") {
+        todo.project = Some(project.trim().to_string());
+        return;
+    }
+    if let Some(date) = planning_value(line, "DEADLINE: <") {
+        todo.due = chrono::NaiveDate::parse_from_str(date.get(..10).unwrap_or(date), "%Y-%m-%d").ok();
+    }
+    if let Some(date) = planning_value(line, "SCHEDULED: <") {
+        todo.scheduled = chrono::NaiveDate::parse_from_str(date.get(..10).unwrap_or(date), "%Y-%m-%d").ok();
+    }
+    if let Some(stamp) = planning_value(line, "CLOSED: [") {
+        // `2025-01-31 Fri 09:30`, day name skipped
+        let mut parts = stamp.split_whitespace();
+        if let (Some(date), _, Some(time)) = (parts.next(), parts.next(), parts.next()) {
+            if let Ok(closed) = NaiveDateTime::parse_from_str(&format!("{} {}", date, time), "%Y-%m-%d %H:%M") {
+                todo.completed_at = Local.from_local_datetime(&closed).earliest();
+            }
+        }
+    }
+}
+
+// The text between `prefix` and the matching closing bracket
+fn planning_value<'a>(line: &'a str, prefix: &str) -> Option<&'a str> {
+    let start = line.find(prefix)? + prefix.len();
+    let rest = &line[start..];
+    let end = rest.find(['>', ']'])?;
+    Some(&rest[..end])
+}
+
+fn priority_letter(priority: Priority) -> char {
+    match priority {
+        Priority::High => 'A',
+        Priority::Medium => 'B',
+        Priority::Low => 'C',
+    }
+}
@@ -0,0 +1,97 @@
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::state::todo::Todo;
+use crate::utils::storage::TodoStore;
+
+/// Keeps each todo as a JSON row so saves only touch what changed
+pub struct SqliteStore {
+    path: PathBuf,
+    connection: Connection,
+    // Row contents as of the last load or save, keyed by todo id
+    saved: HashMap<usize, (usize, String)>,
+    // `PRAGMA data_version` as of the last load or save; it changes when
+    // another connection commits
+    data_version: i64,
+}
+
+impl SqliteStore {
+    pub fn open(path: PathBuf) -> Result<Self> {
+        let connection = Connection::open(&path)
+            .with_context(|| format!("failed to open {}", path.display()))?;
+        connection
+            .execute_batch(
+                "CREATE TABLE IF NOT EXISTS todos (
+                    id INTEGER PRIMARY KEY,
+                    position INTEGER NOT NULL,
+                    data TEXT NOT NULL
+                );",
+            )
+            .context("failed to create schema")?;
+        Ok(Self { path, connection, saved: HashMap::new(), data_version: 0 })
+    }
+
+    fn data_version(&self) -> Result<i64> {
+        self.connection
+            .query_row("PRAGMA data_version", [], |row| row.get(0))
+            .context("failed to read data version")
+    }
+}
+
+impl TodoStore for SqliteStore {
+    fn load(&mut self) -> Result<Vec<Todo>> {
+        let mut statement = self
+            .connection
+            .prepare("SELECT position, data FROM todos ORDER BY position")?;
+        let rows: Vec<(i64, String)> = statement
+            .query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let mut todos = Vec::with_capacity(rows.len());
+        self.saved.clear();
+        for (position, data) in rows {
+            let todo: Todo = serde_json::from_str(&data).context("failed to deserialize todo")?;
+            self.saved.insert(todo.id, (position as usize, data));
+            todos.push(todo);
+        }
+        self.data_version = self.data_version()?;
+        Ok(todos)
+    }
+
+    fn save(&mut self, todos: &[Todo]) -> Result<()> {
+        let mut current = HashMap::with_capacity(todos.len());
+        for (position, todo) in todos.iter().enumerate() {
+            let data = serde_json::to_string(todo).context("failed to serialize todo")?;
+            current.insert(todo.id, (position, data));
+        }
+
+        let transaction = self.connection.transaction()?;
+        for (id, row) in &current {
+            if self.saved.get(id) != Some(row) {
+                transaction.execute(
+                    "INSERT INTO todos (id, position, data) VALUES (?1, ?2, ?3)
+                     ON CONFLICT(id) DO UPDATE SET position = excluded.position, data = excluded.data",
+                    params![*id as i64, row.0 as i64, row.1],
+                )?;
+            }
+        }
+        for id in self.saved.keys().filter(|id| !current.contains_key(id)) {
+            transaction.execute("DELETE FROM todos WHERE id = ?1", params![*id as i64])?;
+        }
+        transaction.commit().context("failed to commit todos")?;
+
+        self.saved = current;
+        self.data_version = self.data_version()?;
+        Ok(())
+    }
+
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn changed_externally(&self) -> bool {
+        self.data_version().is_ok_and(|version| version != self.data_version)
+    }
+}
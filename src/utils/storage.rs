@@ -1,41 +1,451 @@
 use anyhow::{Result,Context};
 use serde_json;
+use std::collections::BTreeMap;
 use std::fs::{self, File};
 use std::io::{Read, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use chrono::Local;
+use tempfile::NamedTempFile;
 
 use crate::state::todo::Todo;
+use crate::state::workflow::{DEFAULT_STATE, DONE_STATE};
+use crate::state::filter::SavedView;
+use crate::state::history::History;
+use crate::state::sort::SortMode;
+use crate::state::archive::RemovedTodo;
+use crate::utils::config::{Backend, OnLocked, StorageConfig};
+use crate::utils::lock::{LockOwner, LockState, StoreLock};
 use serde::{Serialize, Deserialize};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+/// Somewhere todos can be loaded from and saved to
+pub trait TodoStore {
+    fn load(&mut self) -> Result<Vec<Todo>>;
+    fn save(&mut self, todos: &[Todo]) -> Result<()>;
+    fn path(&self) -> &Path;
+
+    /// Loads the store again after an outside change. Unlike `load` it never
+    /// falls back to a backup: a file caught mid-write is an error, and the
+    /// change stays pending so the next check retries.
+    fn reload(&mut self) -> Result<Vec<Todo>> {
+        self.load()
+    }
+
+    /// The backup the last `load` fell back to, if the main data was unreadable
+    fn recovered_from(&self) -> Option<&Path> {
+        None
+    }
+
+    /// Whether something else wrote to the store since our last load or save
+    fn changed_externally(&self) -> bool {
+        false
+    }
+
+    /// The instance holding the lock, if this one may only read
+    fn read_only(&self) -> Option<&LockOwner> {
+        None
+    }
+}
+
+/// A backend together with its lock, released when the store is dropped
+struct LockedStore {
+    store: Box<dyn TodoStore>,
+    lock: LockState,
+}
+
+impl TodoStore for LockedStore {
+    fn load(&mut self) -> Result<Vec<Todo>> {
+        self.store.load()
+    }
+
+    fn reload(&mut self) -> Result<Vec<Todo>> {
+        self.store.reload()
+    }
+
+    fn save(&mut self, todos: &[Todo]) -> Result<()> {
+        if let Some(owner) = self.read_only() {
+            anyhow::bail!("{} is in use by {}; not saving", self.path().display(), owner);
+        }
+        self.store.save(todos)
+    }
+
+    fn path(&self) -> &Path {
+        self.store.path()
+    }
+
+    fn recovered_from(&self) -> Option<&Path> {
+        self.store.recovered_from()
+    }
+
+    fn changed_externally(&self) -> bool {
+        self.store.changed_externally()
+    }
+
+    fn read_only(&self) -> Option<&LockOwner> {
+        match &self.lock {
+            LockState::Acquired(_) => None,
+            LockState::HeldBy(owner) => Some(owner),
+        }
+    }
+}
+
+/// Version of the todo file layout this build writes
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// Upgrades a file from version `i` to `i + 1`; run in order by `migrate`
+const MIGRATIONS: [fn(Value) -> Result<Value>; SCHEMA_VERSION as usize] = [
+    // 0 -> 1: a bare array of todos becomes a versioned envelope
+    |todos| Ok(serde_json::json!({ "version": 1, "todos": todos })),
+    // 1 -> 2: the `completed` flag becomes a workflow `state`
+    |mut file| {
+        if let Some(todos) = file.get_mut("todos").and_then(Value::as_array_mut) {
+            for todo in todos.iter_mut().filter_map(Value::as_object_mut) {
+                if !todo.contains_key("state") {
+                    let done = todo.get("completed").and_then(Value::as_bool) == Some(true);
+                    todo.insert("state".to_string(), Value::from(if done { DONE_STATE } else { DEFAULT_STATE }));
+                }
+            }
+        }
+        file["version"] = Value::from(2);
+        Ok(file)
+    },
+];
+
+/// The versioned envelope todo files are stored in
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TodoFile {
+    pub version: u32,
+    pub todos: Vec<Todo>,
+    /// Top-level fields written by newer versions, kept as they are
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, Value>,
+}
+
+impl TodoFile {
+    pub fn new(todos: Vec<Todo>) -> Self {
+        Self { version: SCHEMA_VERSION, todos, extra: BTreeMap::new() }
+    }
+}
+
+/// The version a stored value was written with. Files from before the
+/// envelope are bare arrays, which count as version 0.
+fn schema_version(value: &Value) -> Result<u32> {
+    match value {
+        Value::Array(_) => Ok(0),
+        Value::Object(map) => map
+            .get("version")
+            .and_then(Value::as_u64)
+            .map(|v| v as u32)
+            .context("todo file has no version"),
+        _ => anyhow::bail!("todo file is neither a list nor an object"),
+    }
+}
+
+/// Runs every migration between the stored version and `SCHEMA_VERSION`.
+/// Files from a newer version are left alone; unknown fields survive anyway.
+fn migrate(mut value: Value) -> Result<Value> {
+    let mut version = schema_version(&value)?;
+    if version > SCHEMA_VERSION {
+        log::warn!("todo file has schema version {}, newer than {}", version, SCHEMA_VERSION);
+    }
+    while version < SCHEMA_VERSION {
+        value = MIGRATIONS[version as usize](value)
+            .with_context(|| format!("failed to migrate todo file from version {}", version))?;
+        version = schema_version(&value)?;
+    }
+    Ok(value)
+}
+
+/// Serialization used by `FileStore`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FileFormat {
+    Json,
+    Yaml,
+}
+
+impl FileFormat {
+    fn serialize(&self, file: &TodoFile) -> Result<String> {
+        match self {
+            FileFormat::Json => serde_json::to_string_pretty(file).context("failed to serialize"),
+            FileFormat::Yaml => serde_yaml::to_string(file).context("failed to serialize"),
+        }
+    }
+
+    fn deserialize(&self, contents: &str) -> Result<TodoFile> {
+        // Both formats go through a JSON value so one migration chain serves both
+        let value: Value = match self {
+            FileFormat::Json => serde_json::from_str(contents).context("failed to parse todos")?,
+            FileFormat::Yaml => serde_yaml::from_str(contents).context("failed to parse todos")?,
+        };
+        serde_json::from_value(migrate(value)?).context("failed to deserialize todos")
+    }
+}
+
+/// A whole-file store rewritten atomically on every save, with backups
+pub struct FileStore {
+    path: PathBuf,
+    format: FileFormat,
+    recovered_from: Option<PathBuf>,
+    /// Version and unknown fields of the loaded file, written back on save
+    version: u32,
+    extra: BTreeMap<String, Value>,
+    /// Modification time as of our last load or save
+    modified: Option<SystemTime>,
+}
+
+impl FileStore {
+    pub fn new(path: PathBuf, format: FileFormat) -> Self {
+        Self { path, format, recovered_from: None, version: SCHEMA_VERSION, extra: BTreeMap::new(), modified: None }
+    }
+
+    fn modified_time(&self) -> Option<SystemTime> {
+        fs::metadata(&self.path).and_then(|m| m.modified()).ok()
+    }
+}
+
+impl TodoStore for FileStore {
+    fn load(&mut self) -> Result<Vec<Todo>> {
+        // Taken first so a file that fails to parse is not retried until it
+        // changes again
+        self.modified = self.modified_time();
+        let (file, recovered_from) = TodoStorage::load_file_with_recovery(&self.path, self.format)?;
+        self.recovered_from = recovered_from;
+        // Never write a newer file back with an older version number
+        self.version = file.version.max(SCHEMA_VERSION);
+        self.extra = file.extra;
+        Ok(file.todos)
+    }
+
+    fn reload(&mut self) -> Result<Vec<Todo>> {
+        let modified = self.modified_time();
+        let file = TodoStorage::load_file(&self.path, self.format)?;
+        self.modified = modified;
+        self.recovered_from = None;
+        self.version = file.version.max(SCHEMA_VERSION);
+        self.extra = file.extra;
+        Ok(file.todos)
+    }
+
+    fn save(&mut self, todos: &[Todo]) -> Result<()> {
+        let file = TodoFile { version: self.version, todos: todos.to_vec(), extra: self.extra.clone() };
+        TodoStorage::save_file(&file, &self.path, self.format)?;
+        self.modified = self.modified_time();
+        Ok(())
+    }
+
+    fn changed_externally(&self) -> bool {
+        self.modified_time() != self.modified
+    }
+
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn recovered_from(&self) -> Option<&Path> {
+        self.recovered_from.as_deref()
+    }
+}
 
 #[derive(Serialize, Deserialize)]
 pub struct TodoStorage;
 
 impl TodoStorage {
-    pub fn save_todos(todos: &[Todo], path: &Path) -> Result<()> {
-        let json = serde_json::to_string_pretty(todos)
-        .context("failde to serialize")?;
-        let mut file = File::create(path)
-        .context("Failed to create file")?;
-        file.write_all(json.as_bytes())
-        .context("failed to write")?;
-        
+    /// How many timestamped copies of the todo file are kept
+    pub const BACKUP_COUNT: usize = 5;
+
+    /// Opens the backend chosen in the config at `path` and locks it. If
+    /// another instance holds the lock the store is read-only, or opening
+    /// fails when the config says to refuse.
+    pub fn open(config: &StorageConfig, path: PathBuf) -> Result<Box<dyn TodoStore>> {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)
+            .with_context(|| format!("failed to create {}", dir.display()))?;
+        }
+        let lock = StoreLock::acquire(&path)?;
+        if let (LockState::HeldBy(owner), OnLocked::Refuse) = (&lock, config.on_locked) {
+            anyhow::bail!(
+                "{} is in use by {}; if that is wrong, delete {}",
+                path.display(), owner, StoreLock::path_for(&path).display()
+            );
+        }
+        let store: Box<dyn TodoStore> = match config.backend {
+            Backend::Json => Box::new(FileStore::new(path, FileFormat::Json)),
+            Backend::Yaml => Box::new(FileStore::new(path, FileFormat::Yaml)),
+            #[cfg(feature = "sqlite")]
+            Backend::Sqlite => Box::new(crate::utils::sqlite::SqliteStore::open(path)?),
+            #[cfg(not(feature = "sqlite"))]
+            Backend::Sqlite => anyhow::bail!("this build has no SQLite support; rebuild without --no-default-features, or with --features sqlite"),
+        };
+        Ok(Box::new(LockedStore { store, lock }))
+    }
 
+    pub fn save_file(file: &TodoFile, path: &Path, format: FileFormat) -> Result<()> {
+        let contents = format.serialize(file)?;
+        Self::backup(path)
+        .context("failed to back up todos")?;
+        Self::write_atomic(path, contents.as_bytes())
+        .context("failed to write todos")?;
         Ok(())
+    }
+
+    /// Like `load_file`, but falls back to the newest backup that still
+    /// deserializes. Returns the backup used, if any.
+    pub fn load_file_with_recovery(path: &Path, format: FileFormat) -> Result<(TodoFile, Option<PathBuf>)> {
+        let error = match Self::load_file(path, format) {
+            Ok(file) => return Ok((file, None)),
+            Err(e) => e,
+        };
+        for backup in Self::backups(path)? {
+            if let Ok(file) = Self::load_file(&backup, format) {
+                log::warn!("Recovered todos from {}: {:#}", backup.display(), error);
+                return Ok((file, Some(backup)));
+            }
+        }
+        Err(error)
+    }
 
+    /// Writes to a temp file in the same directory, syncs it and renames it
+    /// over `path`, so a crash leaves either the old or the new contents.
+    pub fn write_atomic(path: &Path, bytes: &[u8]) -> Result<()> {
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        let mut temp = NamedTempFile::new_in(dir)
+        .context("failed to create temp file")?;
+        temp.write_all(bytes)
+        .context("failed to write temp file")?;
+        temp.as_file().sync_all()
+        .context("failed to sync temp file")?;
+        temp.persist(path)
+        .context("failed to replace file")?;
+        // Make the rename itself durable
+        if let Ok(dir) = File::open(dir) {
+            let _ = dir.sync_all();
+        }
+        Ok(())
     }
 
-    pub fn load_todos(path: &Path) -> Result<Vec<Todo>> {
+    // Copies the current file to `<name>.<timestamp>.bak` and prunes old copies
+    fn backup(path: &Path) -> Result<()> {
         if !path.exists() {
+            return Ok(());
+        }
+        let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+            return Ok(());
+        };
+        let stamp = Local::now().format("%Y%m%d-%H%M%S%.3f");
+        fs::copy(path, path.with_file_name(format!("{}.{}.bak", name, stamp)))?;
+        for old in Self::backups(path)?.into_iter().skip(Self::BACKUP_COUNT) {
+            fs::remove_file(old)?;
+        }
+        Ok(())
+    }
+
+    /// Backups of `path`, newest first
+    pub fn backups(path: &Path) -> Result<Vec<PathBuf>> {
+        let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
             return Ok(Vec::new());
+        };
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        let prefix = format!("{}.", name);
+        let mut backups: Vec<PathBuf> = fs::read_dir(dir)?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|p| {
+                p.file_name()
+                    .and_then(|n| n.to_str())
+                    .is_some_and(|n| n.starts_with(&prefix) && n.ends_with(".bak"))
+            })
+            .collect();
+        // The timestamp format sorts lexically
+        backups.sort();
+        backups.reverse();
+        Ok(backups)
+    }
+
+    pub fn load_todos(path: &Path, format: FileFormat) -> Result<Vec<Todo>> {
+        Ok(Self::load_file(path, format)?.todos)
+    }
+
+    /// Reads a todo file of any known version, migrating it to the current one
+    pub fn load_file(path: &Path, format: FileFormat) -> Result<TodoFile> {
+        if !path.exists() {
+            return Ok(TodoFile::new(Vec::new()));
         }
         let mut file = File::open(path)
         .context("failed to open storage")?;
         let mut contents = String::new();
         file.read_to_string(&mut contents)
         .context("failed to read ")?;
-        let todos = serde_json::from_str(&contents)
-        .context("failed to deserialize todos")?;
-        
-        Ok(todos)
+        let todo_file = format.deserialize(&contents)?;
+
+        Ok(todo_file)
+    }
+
+    pub fn save_views(views: &[SavedView], path: &Path) -> Result<()> {
+        Self::save_json(&views, path).context("failed to save views")
+    }
+
+    pub fn load_views(path: &Path) -> Result<Vec<SavedView>> {
+        Self::load_json(path).context("failed to load views")
+    }
+
+    pub fn save_lists(lists: &[String], path: &Path) -> Result<()> {
+        Self::save_json(&lists, path).context("failed to save lists")
+    }
+
+    pub fn load_lists(path: &Path) -> Result<Vec<String>> {
+        Self::load_json(path).context("failed to load lists")
+    }
+
+    pub fn save_sort_mode(sort: SortMode, path: &Path) -> Result<()> {
+        Self::save_json(&sort, path).context("failed to save sort mode")
+    }
+
+    pub fn load_sort_mode(path: &Path) -> Result<SortMode> {
+        Self::load_json(path).context("failed to load sort mode")
+    }
+
+    /// Saves the archive or trash bin
+    pub fn save_removed(entries: &[RemovedTodo], path: &Path) -> Result<()> {
+        Self::save_json(&entries, path).context("failed to save removed todos")
+    }
+
+    pub fn load_removed(path: &Path) -> Result<Vec<RemovedTodo>> {
+        Self::load_json(path).context("failed to load removed todos")
+    }
+
+    pub fn save_history(history: &History, path: &Path) -> Result<()> {
+        Self::save_json(history, path).context("failed to save history")
+    }
+
+    pub fn load_history(path: &Path) -> Result<History> {
+        Self::load_json(path).context("failed to load history")
+    }
+
+    fn save_json<T: Serialize>(value: &T, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(value)
+        .context("failed to serialize")?;
+        Self::write_atomic(path, json.as_bytes())
+        .context("failed to write")?;
+        Ok(())
+    }
+
+    // A missing file loads as the type's default
+    fn load_json<T: DeserializeOwned + Default>(path: &Path) -> Result<T> {
+        if !path.exists() {
+            return Ok(T::default());
+        }
+        let contents = fs::read_to_string(path)
+        .context("failed to read")?;
+        let value = serde_json::from_str(&contents)
+        .context("failed to deserialize")?;
+        Ok(value)
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,332 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local, NaiveDate, TimeZone};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+use crate::state::todo::{has_children, Priority, Recurrence, Todo};
+
+/// Reads a todo.txt file (http://todotxt.org). Ids come from `id:` tags
+/// where present and are otherwise numbered from the line order.
+pub fn read(path: &Path) -> Result<Vec<Todo>> {
+    let contents = fs::read_to_string(path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    Ok(parse(&contents))
+}
+
+pub fn write(todos: &[Todo], path: &Path) -> Result<()> {
+    fs::write(path, format(todos))
+        .with_context(|| format!("failed to write {}", path.display()))
+}
+
+pub fn parse(contents: &str) -> Vec<Todo> {
+    let mut todos: Vec<Todo> = contents
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(parse_line)
+        .collect();
+
+    // Number the todos that had no `id:` tag after the highest explicit one
+    let taken: HashSet<usize> = todos.iter().map(|t| t.id).filter(|&id| id != 0).collect();
+    let mut next_id = taken.iter().max().copied().unwrap_or(0) + 1;
+    for todo in todos.iter_mut().filter(|t| t.id == 0) {
+        todo.id = next_id;
+        next_id += 1;
+    }
+    todos
+}
+
+pub fn format(todos: &[Todo]) -> String {
+    let mut contents = String::new();
+    for todo in todos {
+        contents.push_str(&format_line(todo, has_children(todos, todo.id)));
+        contents.push('\n');
+    }
+    contents
+}
+
+/// Parses one line; the id is left at 0 unless the line has an `id:` tag
+pub fn parse_line(line: &str) -> Todo {
+    let mut todo = Todo::new(0, String::new());
+    let mut words = line.split_whitespace().peekable();
+
+    let done = words.peek() == Some(&"x");
+    if done {
+        words.next();
+        todo.set_done(true);
+    } else if let Some(word) = words.peek().filter(|w| parse_priority(w).is_some()) {
+        set_priority(&mut todo, &word[1..2]);
+        words.next();
+    }
+    let first = words.peek().and_then(|w| parse_day(w));
+    if first.is_some() {
+        words.next();
+    }
+    // A done line has the completion date first when it has both; a lone
+    // date is the creation date, as written for todos with no completion date
+    let second = words.peek().and_then(|w| parse_day(w)).filter(|_| done);
+    if second.is_some() {
+        words.next();
+    }
+    match (first, second) {
+        (Some(completed), Some(created)) => {
+            todo.completed_at = Some(start_of_day(completed));
+            todo.created_at = start_of_day(created);
+        }
+        (Some(created), None) => todo.created_at = start_of_day(created),
+        _ => {}
+    }
+
+    // Unknown `key:value` words only count as extensions in the run of tokens
+    // closing the line; earlier ones are part of the title
+    let words: Vec<&str> = words.collect();
+    let is_token = |word: &str| {
+        word.strip_prefix('+').is_some_and(|p| !p.is_empty())
+            || word.strip_prefix('@').is_some_and(|c| !c.is_empty())
+            || split_extension(word).is_some()
+    };
+    let tail = words.len() - words.iter().rev().take_while(|word| is_token(word)).count();
+
+    let mut title = Vec::new();
+    for (index, word) in words.into_iter().enumerate() {
+        if let Some(project) = word.strip_prefix('+').filter(|p| !p.is_empty()) {
+            // Only one project fits the model; later ones stay in the title
+            if todo.project.is_none() {
+                todo.project = Some(project.to_string());
+                continue;
+            }
+        } else if let Some(context) = word.strip_prefix('@').filter(|c| !c.is_empty()) {
+            if !todo.tags.iter().any(|t| t == context) {
+                todo.tags.push(context.to_string());
+            }
+            continue;
+        } else if let Some((key, value)) = split_extension(word) {
+            if apply_extension(&mut todo, key, value, index >= tail) {
+                continue;
+            }
+        }
+        title.push(word);
+    }
+    todo.title = title.join(" ");
+    todo
+}
+
+pub fn format_line(todo: &Todo, has_children: bool) -> String {
+    let mut parts = Vec::new();
+    if todo.is_done() {
+        parts.push("x".to_string());
+        if let Some(completed_at) = todo.completed_at {
+            parts.push(completed_at.format("%Y-%m-%d").to_string());
+        }
+    } else if todo.priority.is_some() {
+        parts.push(format!("({})", priority_letter(todo)));
+    }
+    parts.push(todo.created_at.format("%Y-%m-%d").to_string());
+    if !todo.title.is_empty() {
+        parts.push(todo.title.clone());
+    }
+    if let Some(project) = &todo.project {
+        parts.push(format!("+{}", project));
+    }
+    for tag in &todo.tags {
+        parts.push(format!("@{}", tag));
+    }
+    // Completed lines drop the leading priority, so keep it as a tag
+    if todo.is_done() && todo.priority.is_some() {
+        parts.push(format!("pri:{}", priority_letter(todo)));
+    }
+    if let Some(due) = todo.due {
+        parts.push(format!("due:{}", due.format("%Y-%m-%d")));
+    }
+    if let Some(scheduled) = todo.scheduled {
+        parts.push(format!("t:{}", scheduled.format("%Y-%m-%d")));
+    }
+    if let Some(recurrence) = &todo.recurrence {
+        parts.push(format!("rec:{}", recurrence));
+    }
+    if has_children {
+        parts.push(format!("id:{}", todo.id));
+    }
+    if let Some(parent_id) = todo.parent_id {
+        parts.push(format!("parent:{}", parent_id));
+    }
+    // The exact letter of a `(D)`-`(Z)` priority is written as the priority
+    for (key, value) in todo.attributes.iter().filter(|(key, _)| *key != PRIORITY_LETTER) {
+        parts.push(format!("{}:{}", key, value));
+    }
+    parts.join(" ")
+}
+
+// Handles the extensions that map onto `Todo` fields, and stores the rest
+// when `store_unknown` is set
+fn apply_extension(todo: &mut Todo, key: &str, value: &str, store_unknown: bool) -> bool {
+    match key {
+        "due" => match parse_day(value) {
+            Some(date) => todo.due = Some(date),
+            None => return false,
+        },
+        "t" => match parse_day(value) {
+            Some(date) => todo.scheduled = Some(date),
+            None => return false,
+        },
+        "rec" => match Recurrence::parse(value) {
+            Some(recurrence) => todo.recurrence = Some(recurrence),
+            None => return false,
+        },
+        "pri" => match parse_priority(&format!("({})", value)) {
+            Some(_) => set_priority(todo, value),
+            None => return false,
+        },
+        "id" => match value.parse() {
+            Ok(id) => todo.id = id,
+            Err(_) => return false,
+        },
+        "parent" => match value.parse() {
+            Ok(parent_id) => todo.parent_id = Some(parent_id),
+            Err(_) => return false,
+        },
+        _ if store_unknown => {
+            todo.attributes.insert(key.to_string(), value.to_string());
+        }
+        _ => return false,
+    }
+    true
+}
+
+// `key:value` with no empty halves; URLs such as `https://...` and times
+// like `10:30:00` are left alone. `rec:weekly:mon` is the one nested value.
+fn split_extension(word: &str) -> Option<(&str, &str)> {
+    let (key, value) = word.split_once(':')?;
+    if key.is_empty() || value.is_empty() || value.starts_with("//") {
+        return None;
+    }
+    if value.contains(':') && key != "rec" {
+        return None;
+    }
+    Some((key, value))
+}
+
+fn parse_priority(word: &str) -> Option<Priority> {
+    let letter = word.strip_prefix('(')?.strip_suffix(')')?;
+    match letter {
+        "A" => Some(Priority::High),
+        "B" => Some(Priority::Medium),
+        _ if letter.len() == 1 && letter.chars().all(|c| c.is_ascii_uppercase()) => Some(Priority::Low),
+        _ => None,
+    }
+}
+
+/// Attribute keeping the letter of priorities below `(C)`, which all map to
+/// `Priority::Low`
+const PRIORITY_LETTER: &str = "pri";
+
+// `letter` is a checked `A`-`Z`
+fn set_priority(todo: &mut Todo, letter: &str) {
+    todo.priority = parse_priority(&format!("({})", letter));
+    if letter > "C" {
+        todo.attributes.insert(PRIORITY_LETTER.to_string(), letter.to_string());
+    } else {
+        todo.attributes.remove(PRIORITY_LETTER);
+    }
+}
+
+fn priority_letter(todo: &Todo) -> String {
+    match todo.priority {
+        Some(Priority::High) => "A".to_string(),
+        Some(Priority::Medium) => "B".to_string(),
+        _ => todo.attributes.get(PRIORITY_LETTER).cloned().unwrap_or_else(|| "C".to_string()),
+    }
+}
+
+fn parse_day(word: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(word, "%Y-%m-%d").ok()
+}
+
+fn start_of_day(date: NaiveDate) -> DateTime<Local> {
+    Local
+        .from_local_datetime(&date.and_hms_opt(0, 0, 0).unwrap_or_default())
+        .earliest()
+        .unwrap_or_else(Local::now)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // parse -> format -> parse must give the same line and the same todo
+    fn round_trip(line: &str) -> (Todo, String) {
+        let todo = parse_line(line);
+        let formatted = format_line(&todo, false);
+        let again = parse_line(&formatted);
+        assert_eq!(format_line(&again, false), formatted, "line changed on the second pass");
+        assert_eq!(again.title, todo.title);
+        assert_eq!(again.is_done(), todo.is_done());
+        assert_eq!(again.completed_at, todo.completed_at);
+        assert_eq!(again.created_at, todo.created_at);
+        assert_eq!(again.priority, todo.priority);
+        assert_eq!(again.attributes, todo.attributes);
+        (again, formatted)
+    }
+
+    #[test]
+    fn open_todo_keeps_fields() {
+        let (todo, line) = round_trip("(A) 2025-01-02 Call mom +family @phone due:2025-01-31 t:2025-01-20 rec:weekly");
+        assert_eq!(line, "(A) 2025-01-02 Call mom +family @phone due:2025-01-31 t:2025-01-20 rec:weekly");
+        assert_eq!(todo.project.as_deref(), Some("family"));
+        assert_eq!(todo.tags, vec!["phone"]);
+        assert!(todo.due.is_some() && todo.scheduled.is_some() && todo.recurrence.is_some());
+    }
+
+    #[test]
+    fn completed_todo_keeps_both_dates() {
+        let (todo, line) = round_trip("x 2025-01-05 2025-01-02 Pay rent pri:B");
+        assert_eq!(line, "x 2025-01-05 2025-01-02 Pay rent pri:B");
+        assert_eq!(todo.completed_at.unwrap().date_naive(), parse_day("2025-01-05").unwrap());
+        assert_eq!(todo.priority, Some(Priority::Medium));
+    }
+
+    #[test]
+    fn completed_without_date_does_not_invent_one() {
+        let mut todo = parse_line("2025-01-02 Pay rent");
+        todo.set_done(true);
+        let line = format_line(&todo, false);
+        assert_eq!(line, "x 2025-01-02 Pay rent");
+        assert_eq!(parse_line(&line).completed_at, None);
+    }
+
+    #[test]
+    fn completed_with_only_the_created_date_keeps_it() {
+        let (todo, line) = round_trip("x 2025-01-02 Pay rent @home");
+        assert_eq!(line, "x 2025-01-02 Pay rent @home");
+        assert_eq!(todo.completed_at, None);
+        assert_eq!(todo.created_at.date_naive(), parse_day("2025-01-02").unwrap());
+    }
+
+    #[test]
+    fn key_value_words_in_the_title_stay_in_place() {
+        let (todo, line) = round_trip("2025-01-02 Set ratio:16 in config @work owner:me");
+        assert_eq!(todo.title, "Set ratio:16 in config");
+        assert_eq!(todo.attributes.get("owner").map(String::as_str), Some("me"));
+        assert_eq!(line, "2025-01-02 Set ratio:16 in config @work owner:me");
+    }
+
+    #[test]
+    fn low_priorities_keep_their_letter() {
+        for letter in ["C", "D", "Z"] {
+            let (todo, line) = round_trip(&format!("({}) 2025-01-02 Someday", letter));
+            assert_eq!(todo.priority, Some(Priority::Low));
+            assert_eq!(line, format!("({}) 2025-01-02 Someday", letter));
+        }
+        let (_, line) = round_trip("x 2025-01-05 2025-01-02 Someday pri:E");
+        assert_eq!(line, "x 2025-01-05 2025-01-02 Someday pri:E");
+    }
+
+    #[test]
+    fn subtasks_round_trip_through_ids() {
+        let todos = parse("2025-01-02 Parent id:7\n2025-01-02 Child parent:7\n");
+        let again = parse(&format(&todos));
+        assert_eq!(again[0].id, 7);
+        assert_eq!(again[1].parent_id, Some(7));
+        assert_eq!(format(&again), format(&todos));
+    }
+}